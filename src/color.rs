@@ -0,0 +1,89 @@
+//! Whether terminal output is colored, following `--color` and `NO_COLOR`
+//!
+//! With `auto`, a stream is colored if it is a terminal and `NO_COLOR` is unset
+//! or empty, so logs redirected to files stay plain.
+
+use crate::ViewerError;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STDOUT: AtomicBool = AtomicBool::new(false);
+static STDERR: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            },
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(ViewerError::Other(format!(
+                "unknown color choice {:?}, expected one of auto, always, never",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Color {
+    Red = 31,
+    Green = 32,
+    Yellow = 33,
+}
+
+/// Decides once whether stdout and stderr are colored
+pub fn init(choice: ColorChoice) {
+    STDOUT.store(
+        choice.enabled(io::stdout().is_terminal()),
+        Ordering::Relaxed,
+    );
+    STDERR.store(
+        choice.enabled(io::stderr().is_terminal()),
+        Ordering::Relaxed,
+    );
+}
+
+pub fn stderr_enabled() -> bool {
+    STDERR.load(Ordering::Relaxed)
+}
+
+fn wrap(text: &str, color: Color, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b[{}m{}\x1b[0m", color as u8, text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Wraps `text` in the escape codes of `color` if stdout is colored
+pub fn paint(text: &str, color: Color) -> String {
+    wrap(text, color, STDOUT.load(Ordering::Relaxed))
+}
+
+/// Like `paint`, for text written to stderr
+pub fn paint_stderr(text: &str, color: Color) -> String {
+    wrap(text, color, stderr_enabled())
+}
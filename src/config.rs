@@ -0,0 +1,312 @@
+//! Options loaded from a `--config` TOML file
+//!
+//! Precedence of every option is: explicit command line flag, then the config
+//! file, then the `--quality` preset, then the built-in default of the flag.
+//!
+//! Keys are named like the long command line flags, e.g.
+//!
+//! ```toml
+//! size = "1280,720"
+//! frame-rate = 60
+//! point-type = 7
+//! initial-rotation = 30.0
+//! rotation-speed = 0.2
+//! min-bounds = [-1.0, -1.0, -1.0]
+//! max-bounds = [1.0, 1.0, 1.0]
+//! worker = 4
+//! ```
+//!
+//! Only flat `key = value` pairs are understood, with basic strings and their
+//! escapes like `\"`, literal strings, numbers, booleans or arrays of them on
+//! one line. Tables, inline tables and multi-line strings or arrays are
+//! rejected with the line they are on.
+
+use crate::ViewerError;
+use clap::ArgMatches;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Default)]
+pub struct Config {
+    size: Option<String>,
+    frame_rate: Option<String>,
+    point_type: Option<String>,
+    initial_rotation: Option<String>,
+    rotation_speed: Option<String>,
+    min_bounds: Option<String>,
+    max_bounds: Option<String>,
+    worker: Option<String>,
+    /// Values of the `--quality` preset, not read from the file
+    preset: &'static [(&'static str, &'static str)],
+}
+
+impl Config {
+    pub fn read(path: &Path) -> Result<Config, ViewerError> {
+        Config::parse(&fs::read_to_string(path)?)
+            .map_err(|e| ViewerError::Other(format!("{:?}: {}", path, e)))
+    }
+
+    pub fn parse(s: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (n, line) in s.lines().enumerate() {
+            let at_line = |e: String| format!("line {}: {}", n + 1, e);
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(at_line(
+                    "tables are not supported, only flat key = value pairs".to_owned(),
+                ));
+            }
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or_default().trim();
+            let value = parts
+                .next()
+                .ok_or_else(|| at_line("expected key = value".to_owned()))?;
+            let (value, rest) = parse_value(value.trim_start()).map_err(at_line)?;
+            let rest = rest.trim_start();
+            if !rest.is_empty() && !rest.starts_with('#') {
+                return Err(at_line(format!("unexpected {:?} after the value", rest)));
+            }
+            let field = config
+                .field_mut(key)
+                .ok_or_else(|| at_line(format!("unknown key {:?}", key)))?;
+            *field = Some(value);
+        }
+        Ok(config)
+    }
+
+    fn field_mut(&mut self, key: &str) -> Option<&mut Option<String>> {
+        match key {
+            "size" => Some(&mut self.size),
+            "frame-rate" => Some(&mut self.frame_rate),
+            "point-type" => Some(&mut self.point_type),
+            "initial-rotation" => Some(&mut self.initial_rotation),
+            "rotation-speed" => Some(&mut self.rotation_speed),
+            "min-bounds" => Some(&mut self.min_bounds),
+            "max-bounds" => Some(&mut self.max_bounds),
+            "worker" => Some(&mut self.worker),
+            _ => None,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        match key {
+            "size" => self.size.as_deref(),
+            "frame-rate" => self.frame_rate.as_deref(),
+            "point-type" => self.point_type.as_deref(),
+            "initial-rotation" => self.initial_rotation.as_deref(),
+            "rotation-speed" => self.rotation_speed.as_deref(),
+            "min-bounds" => self.min_bounds.as_deref(),
+            "max-bounds" => self.max_bounds.as_deref(),
+            "worker" => self.worker.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn set_preset(&mut self, preset: &'static [(&'static str, &'static str)]) {
+        self.preset = preset;
+    }
+
+    /// Returns the value of argument `name`, merged by precedence
+    pub fn value_of<'a>(&'a self, matches: &'a ArgMatches, name: &str) -> Option<&'a str> {
+        if matches.occurrences_of(name) > 0 {
+            matches.value_of(name)
+        } else {
+            self.get(name)
+                .or_else(|| {
+                    self.preset
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|(_, value)| *value)
+                })
+                .or_else(|| matches.value_of(name))
+        }
+    }
+}
+
+/// Converts the TOML value at the start of `s` into the textual form of the
+/// command line flag, returning the text after it
+///
+/// Arrays become space separated lists as accepted by `--min-bounds`.
+fn parse_value(s: &str) -> Result<(String, &str), String> {
+    if s.starts_with("\"\"\"") || s.starts_with("'''") {
+        Err("multi-line strings are not supported".to_owned())
+    } else if s.starts_with('"') {
+        parse_basic_string(s)
+    } else if let Some(string) = s.strip_prefix('\'') {
+        match string.find('\'') {
+            Some(end) => Ok((string[..end].to_owned(), &string[end + 1..])),
+            None => Err(format!("unterminated string {}", s)),
+        }
+    } else if let Some(mut rest) = s.strip_prefix('[') {
+        let mut values = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Ok((values.join(" "), rest));
+            }
+            if rest.is_empty() || rest.starts_with('#') {
+                return Err(format!("unterminated array {}", s));
+            }
+            let (value, after) = parse_value(rest)?;
+            values.push(value);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err(format!("unterminated array {}", s));
+            }
+        }
+    } else if s.starts_with('{') {
+        Err("inline tables are not supported".to_owned())
+    } else {
+        let end = s
+            .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == '#')
+            .unwrap_or(s.len());
+        if end == 0 {
+            Err("missing value".to_owned())
+        } else {
+            Ok((s[..end].replace('_', ""), &s[end..]))
+        }
+    }
+}
+
+/// Reads the basic string at the start of `s` up to its closing quote,
+/// resolving escapes, and returns the text after it
+fn parse_basic_string(s: &str) -> Result<(String, &str), String> {
+    let mut string = String::new();
+    let mut chars = s.char_indices().skip(1);
+    let unterminated = || format!("unterminated string {}", s);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((string, &s[i + 1..])),
+            '\\' => {
+                let escaped = match chars.next().ok_or_else(unterminated)?.1 {
+                    '"' => '"',
+                    '\\' => '\\',
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    kind @ ('u' | 'U') => {
+                        let digits = if kind == 'u' { 4 } else { 8 };
+                        let hex: String = chars.by_ref().take(digits).map(|(_, c)| c).collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .filter(|_| hex.len() == digits)
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape \\{}{}", kind, hex))?
+                    },
+                    c => return Err(format!("invalid escape \\{}", c)),
+                };
+                string.push(escaped);
+            },
+            c => string.push(c),
+        }
+    }
+    Err(unterminated())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flat_pairs() {
+        let config = Config::parse(
+            "# render settings\n\
+             size = \"1280,720\"\n\
+             \n\
+             frame-rate = 60 # smooth\n\
+             worker=4\n\
+             rotation-speed = 1_000.5\n",
+        )
+        .unwrap();
+        assert_eq!(config.get("size"), Some("1280,720"));
+        assert_eq!(config.get("frame-rate"), Some("60"));
+        assert_eq!(config.get("worker"), Some("4"));
+        assert_eq!(config.get("rotation-speed"), Some("1000.5"));
+        assert_eq!(config.get("point-type"), None);
+    }
+
+    #[test]
+    fn joins_arrays_with_spaces() {
+        let config = Config::parse("min-bounds = [-1.0, -2, 3.5]\nmax-bounds = []\n").unwrap();
+        assert_eq!(config.get("min-bounds"), Some("-1.0 -2 3.5"));
+        assert_eq!(config.get("max-bounds"), Some(""));
+    }
+
+    #[test]
+    fn keeps_hashes_in_strings() {
+        let config = Config::parse("size = \"1,2 # not a comment\" # comment").unwrap();
+        assert_eq!(config.get("size"), Some("1,2 # not a comment"));
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        let error = |s| Config::parse(s).unwrap_err();
+        assert_eq!(
+            error("size = 1\ncolor = 2"),
+            "line 2: unknown key \"color\""
+        );
+        assert_eq!(error("\nsize"), "line 2: expected key = value");
+        assert_eq!(error("size ="), "line 1: missing value");
+        assert_eq!(error("size = \"1,2"), "line 1: unterminated string \"1,2");
+        assert_eq!(
+            error("min-bounds = [1, 2"),
+            "line 1: unterminated array [1, 2"
+        );
+    }
+
+    #[test]
+    fn resolves_string_escapes() {
+        let value = |s: &str| Config::parse(&format!("size = {}", s)).map(|c| c.size);
+        assert_eq!(value(r#""a\"b\\c""#), Ok(Some("a\"b\\c".to_owned())));
+        assert_eq!(
+            value(r#""\u0041\U00000042\t""#),
+            Ok(Some("AB\t".to_owned()))
+        );
+        assert_eq!(value(r#""\" # \"" # c"#), Ok(Some("\" # \"".to_owned())));
+        assert_eq!(value(r#"'C:\frames'"#), Ok(Some("C:\\frames".to_owned())));
+        assert_eq!(
+            value(r#""\x""#),
+            Err("line 1: invalid escape \\x".to_owned())
+        );
+        assert_eq!(
+            value(r#""\u12""#),
+            Err("line 1: invalid unicode escape \\u12\"".to_owned())
+        );
+        assert_eq!(
+            value(r#""open\""#),
+            Err(r#"line 1: unterminated string "open\""#.to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_syntax_with_its_line() {
+        let error = |s| Config::parse(s).unwrap_err();
+        assert_eq!(
+            error("size = 1\n[render]"),
+            "line 2: tables are not supported, only flat key = value pairs"
+        );
+        assert_eq!(
+            error("size = { w = 1 }"),
+            "line 1: inline tables are not supported"
+        );
+        assert_eq!(
+            error("\n\nsize = \"\"\"1,2"),
+            "line 3: multi-line strings are not supported"
+        );
+        assert_eq!(
+            error("size = 1 2"),
+            "line 1: unexpected \"2\" after the value"
+        );
+        assert_eq!(
+            error("min-bounds = [1, 2\n3]"),
+            "line 1: unterminated array [1, 2"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! Per-point colors of `--color-jitter`, randomized around the color of the
+//! points but the same for a particle in every frame
+
+/// The first line color of gnuplot, jittered for points without a color
+pub const DEFAULT_COLOR: u32 = 0x9400d3;
+
+#[derive(Debug, Clone)]
+pub struct ColorJitter {
+    /// Largest change of each of the red, green and blue channels
+    pub amount: u8,
+    pub seed: u64,
+    /// 1-based column identifying particles, which are told apart by their
+    /// line in the sample file otherwise
+    pub id_column: Option<usize>,
+}
+
+impl ColorJitter {
+    /// The color near `base` of the particle `key`, as `0xRRGGBB`
+    pub fn color(&self, base: u32, key: u64) -> u32 {
+        let hash = splitmix64(self.seed ^ splitmix64(key));
+        let range = 2 * u64::from(self.amount) + 1;
+        (0..3).fold(0, |color, channel| {
+            let shift = 16 - 8 * channel;
+            let offset = ((hash >> (16 * channel)) & 0xffff) % range;
+            let value = ((base >> shift) & 0xff) as i64 + offset as i64 - i64::from(self.amount);
+            color | (value.clamp(0, 0xff) as u32) << shift
+        })
+    }
+}
+
+/// The key of a particle identified by the text of its id column
+pub fn id_key(id: &str) -> u64 {
+    // FNV-1a
+    id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Parses a `#rrggbb` color
+pub fn parse_rgb(color: &str) -> Option<u32> {
+    let hex = color
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))?;
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
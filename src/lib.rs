@@ -0,0 +1,6163 @@
+#[macro_use]
+extern crate clap;
+
+mod color;
+mod config;
+mod interrupt;
+mod jitter;
+mod json;
+mod preview;
+mod progress;
+mod stdin_manifest;
+mod timings;
+mod title;
+mod watch;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use color::Color;
+pub use color::ColorChoice;
+use config::Config;
+pub use jitter::ColorJitter;
+use log::{error, info, warn, LevelFilter};
+use progress::ProgressBar;
+use quick_error::quick_error;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::io::{BufRead, BufReader, BufWriter};
+use std::iter;
+use std::num::ParseFloatError;
+use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{fs, sync};
+use stdin_manifest::ManifestReader;
+use threadpool::ThreadPool;
+use timings::Timings;
+pub use title::TitleFormat;
+use title::DEFAULT_TITLE_FORMAT;
+use watch::Watcher;
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum ViewerError {
+        /// IO Error
+        Io(err: io::Error) {
+            from()
+            cause(err)
+            display("io error: {}", err)
+        }
+        ParseInt(err: ParseIntError) {
+            from()
+            cause(err)
+            display("failed to parse int number: {}", err)
+        }
+        ParseFloat(err: ParseFloatError) {
+            from()
+            cause(err)
+            display("failed to parse float number: {}", err)
+        }
+        Args(err: clap::Error) {
+            from()
+            cause(err)
+            display("{}", err)
+        }
+        Other(s: String) {
+            display("{}", s)
+        }
+        /// Error while rendering one frame
+        Frame(frame: usize, err: Box<ViewerError>) {
+            cause(&**err)
+            display("frame {} failed: {}", frame, err)
+        }
+        /// Frame or render stopped after Ctrl-C
+        Interrupted {
+            display("interrupted")
+        }
+        /// Frames that failed under `--keep-going`, everything else was done
+        PartialFailure(frames: Vec<usize>) {
+            display("frames {:?} failed", frames)
+        }
+    }
+}
+
+impl ViewerError {
+    /// Name of the variant, of the wrapped error for a frame
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ViewerError::Io(_) => "io",
+            ViewerError::ParseInt(_) => "parse_int",
+            ViewerError::ParseFloat(_) => "parse_float",
+            ViewerError::Args(_) => "args",
+            ViewerError::Other(_) => "other",
+            ViewerError::Frame(_, err) => err.kind(),
+            ViewerError::Interrupted => "interrupted",
+            ViewerError::PartialFailure(_) => "partial_failure",
+        }
+    }
+
+    /// Index of the frame the error occurred in, if any
+    pub fn frame(&self) -> Option<usize> {
+        match self {
+            ViewerError::Frame(frame, _) => Some(*frame),
+            _ => None,
+        }
+    }
+
+    /// The error as a JSON object of its kind, message and frame, e.g.
+    /// `{"kind":"io","message":"io error: ...","frame":null}`
+    pub fn to_json(&self) -> String {
+        json::Value::object(vec![
+            ("kind", self.kind().into()),
+            ("message", self.to_string().into()),
+            (
+                "frame",
+                match self.frame() {
+                    Some(frame) => frame.into(),
+                    None => json::Value::Null,
+                },
+            ),
+        ])
+        .to_string()
+    }
+}
+
+/// Container and codec of the assembled video
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    Mp4,
+    Webm,
+    Mkv,
+    Gif,
+    Mov,
+    /// Animated png, lossless unlike gif
+    Apng,
+}
+
+impl VideoFormat {
+    /// Codec passed to ffmpeg through `-c:v`
+    fn codec(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "libx264",
+            VideoFormat::Webm => "libvpx-vp9",
+            VideoFormat::Mkv => "ffv1",
+            VideoFormat::Gif => "gif",
+            VideoFormat::Mov => "qtrle",
+            VideoFormat::Apng => "apng",
+        }
+    }
+
+    /// Whether the codec can encode in two passes
+    fn has_two_pass(self) -> bool {
+        self == VideoFormat::Mp4 || self == VideoFormat::Webm
+    }
+
+    /// Pixel format keeping the alpha channel, if the codec has one
+    fn alpha_pixel_format(self) -> Option<&'static str> {
+        match self {
+            VideoFormat::Mp4 | VideoFormat::Gif => None,
+            VideoFormat::Webm | VideoFormat::Mkv => Some("yuva420p"),
+            VideoFormat::Mov => Some("argb"),
+            VideoFormat::Apng => Some("rgba"),
+        }
+    }
+
+    /// Extension of the output file, also used as the format name
+    fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+            VideoFormat::Mkv => "mkv",
+            VideoFormat::Gif => "gif",
+            VideoFormat::Mov => "mov",
+            VideoFormat::Apng => "png",
+        }
+    }
+
+    /// Whether the output is a single stream that cannot be joined from
+    /// segments without encoding it again
+    fn is_animation(self) -> bool {
+        matches!(self, VideoFormat::Gif | VideoFormat::Apng)
+    }
+}
+
+impl FromStr for VideoFormat {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mp4" => Ok(VideoFormat::Mp4),
+            "webm" => Ok(VideoFormat::Webm),
+            "mkv" => Ok(VideoFormat::Mkv),
+            "gif" => Ok(VideoFormat::Gif),
+            "mov" => Ok(VideoFormat::Mov),
+            "apng" => Ok(VideoFormat::Apng),
+            _ => Err(ViewerError::Other(format!(
+                "unknown video format {:?}, expected one of mp4, webm, mkv, gif, mov, apng",
+                s
+            ))),
+        }
+    }
+}
+
+/// Camera of one keyframe of a `_camera.dat` path
+#[derive(Debug, Clone, Copy)]
+struct CameraKey {
+    frame: usize,
+    elevation: f64,
+    azimuth: f64,
+    zoom: f64,
+}
+
+/// Camera keyframes in increasing frame order
+#[derive(Debug, Clone)]
+struct CameraPath {
+    keys: Vec<CameraKey>,
+}
+
+impl CameraPath {
+    /// Camera of frame `i`, linearly interpolated between the surrounding
+    /// keyframes and held constant before the first and after the last one
+    fn at(&self, i: usize) -> CameraKey {
+        let next = self.keys.iter().position(|k| k.frame >= i);
+        match next {
+            Some(0) => self.keys[0],
+            None => self.keys[self.keys.len() - 1],
+            Some(n) => {
+                let (a, b) = (self.keys[n - 1], self.keys[n]);
+                let t = (i - a.frame) as f64 / (b.frame - a.frame) as f64;
+                let lerp = |x: f64, y: f64| x + (y - x) * t;
+                CameraKey {
+                    frame: i,
+                    elevation: lerp(a.elevation, b.elevation),
+                    azimuth: lerp(a.azimuth, b.azimuth),
+                    zoom: lerp(a.zoom, b.zoom),
+                }
+            },
+        }
+    }
+}
+
+/// Gnuplot terminal frames are rendered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminal {
+    Png,
+    Jpeg,
+    Svg,
+    Pdf,
+}
+
+impl Terminal {
+    /// Extension of the frame files
+    fn extension(self) -> &'static str {
+        match self {
+            Terminal::Png => "png",
+            Terminal::Jpeg => "jpg",
+            Terminal::Svg => "svg",
+            Terminal::Pdf => "pdf",
+        }
+    }
+
+    /// Whether frames are images ffmpeg can encode into a video
+    fn is_raster(self) -> bool {
+        self == Terminal::Png || self == Terminal::Jpeg
+    }
+
+    /// Converts a pixel size into the size option of the terminal
+    ///
+    /// The pdf terminal measures in inches, pixels are converted at 96 dpi.
+    fn size(self, (width, height): (f64, f64)) -> String {
+        match self {
+            Terminal::Png | Terminal::Jpeg | Terminal::Svg => format!("{},{}", width, height),
+            Terminal::Pdf => format!("{}in,{}in", width / 96.0, height / 96.0),
+        }
+    }
+}
+
+impl FromStr for Terminal {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(Terminal::Png),
+            "svg" => Ok(Terminal::Svg),
+            "pdf" => Ok(Terminal::Pdf),
+            _ => Err(ViewerError::Other(format!(
+                "unknown terminal {:?}, expected one of png, svg, pdf",
+                s
+            ))),
+        }
+    }
+}
+
+/// Data axis the view turns around with `--rotate-axis`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotateAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl FromStr for RotateAxis {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(RotateAxis::X),
+            "y" => Ok(RotateAxis::Y),
+            "z" => Ok(RotateAxis::Z),
+            _ => Err(ViewerError::Other(format!(
+                "unknown rotation axis {:?}, expected one of x, y, z",
+                s
+            ))),
+        }
+    }
+}
+
+/// How the rendered frames are sequenced for `--loop`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoopMode {
+    None,
+    Repeat,
+    PingPong,
+}
+
+impl LoopMode {
+    /// Frames in the order they are shown in the video
+    ///
+    /// Ping-pong plays `frames` forward and then backward without repeating the
+    /// turning frames, so the video can loop without a jump.
+    fn sequence(self, frames: &[usize]) -> Vec<usize> {
+        let mut sequence = frames.to_vec();
+        if self == LoopMode::PingPong && frames.len() > 2 {
+            sequence.extend(frames[1..frames.len() - 1].iter().rev());
+        }
+        sequence
+    }
+
+    /// Value of `-loop` of ffmpeg's gif muxer, -1 plays once and 0 forever
+    fn gif_loop(self) -> &'static str {
+        match self {
+            LoopMode::None => "-1",
+            LoopMode::Repeat | LoopMode::PingPong => "0",
+        }
+    }
+
+    /// Value of `-plays` of ffmpeg's apng muxer, 0 plays forever
+    fn apng_plays(self) -> &'static str {
+        match self {
+            LoopMode::None => "1",
+            LoopMode::Repeat | LoopMode::PingPong => "0",
+        }
+    }
+}
+
+impl FromStr for LoopMode {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(LoopMode::None),
+            "repeat" => Ok(LoopMode::Repeat),
+            "pingpong" => Ok(LoopMode::PingPong),
+            _ => Err(ViewerError::Other(format!(
+                "unknown loop mode {:?}, expected one of none, repeat, pingpong",
+                s
+            ))),
+        }
+    }
+}
+
+/// A particle species read from `{i}.NAME.dat`, given as
+/// `NAME:POINTTYPE:COLOR` to `--species`
+#[derive(Debug, Clone)]
+pub struct Species {
+    name: String,
+    point_type: String,
+    color: String,
+}
+
+impl FromStr for Species {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':').map(str::trim);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(name), Some(point_type), Some(color))
+                if !name.is_empty() && !point_type.is_empty() && !color.is_empty() =>
+            {
+                Ok(Species {
+                    name: name.to_owned(),
+                    point_type: point_type.to_owned(),
+                    color: color.to_owned(),
+                })
+            },
+            _ => Err(ViewerError::Other(format!(
+                "species {:?} is not of the form NAME:POINTTYPE:COLOR",
+                s
+            ))),
+        }
+    }
+}
+
+/// Named palettes for `--palette` with their gnuplot definitions
+const PALETTES: &[(&str, &str)] = &[
+    (
+        "viridis",
+        "set palette defined (0 '#440154', 1 '#3b528b', 2 '#21918c', 3 '#5ec962', 4 '#fde725')",
+    ),
+    (
+        "jet",
+        "set palette defined (0 '#000090', 1 '#000fff', 2 '#0090ff', 3 '#0fffee', 4 '#90ff70', \
+         5 '#ffee00', 6 '#ff7000', 7 '#ee0000', 8 '#7f0000')",
+    ),
+    ("hot", "set palette rgbformulae 21,22,23"),
+    ("grayscale", "set palette gray"),
+];
+
+/// Named `--quality` presets with the argument values they default to
+const QUALITY_PRESETS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "draft",
+        &[
+            ("size", "960,540"),
+            ("frame-rate", "24"),
+            ("point-size", "1.5"),
+            ("crf", "32"),
+        ],
+    ),
+    (
+        "normal",
+        &[
+            ("size", "1920,1080"),
+            ("frame-rate", "30"),
+            ("point-size", "1"),
+            ("crf", "23"),
+        ],
+    ),
+    (
+        "high",
+        &[
+            ("size", "3840,2160"),
+            ("frame-rate", "60"),
+            ("point-size", "0.5"),
+            ("crf", "18"),
+        ],
+    ),
+];
+
+fn quality_preset(name: &str) -> Result<&'static [(&'static str, &'static str)], ViewerError> {
+    QUALITY_PRESETS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, preset)| *preset)
+        .ok_or_else(|| {
+            let names: Vec<&str> = QUALITY_PRESETS.iter().map(|(n, _)| *n).collect();
+            ViewerError::Other(format!(
+                "unknown quality {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+fn palette_definition(name: &str) -> Result<&'static str, ViewerError> {
+    PALETTES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, definition)| *definition)
+        .ok_or_else(|| {
+            let names: Vec<&str> = PALETTES.iter().map(|(n, _)| *n).collect();
+            ViewerError::Other(format!(
+                "unknown palette {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+/// Min and max bounds, one value per axis each
+pub type Bounds = (Vec<f64>, Vec<f64>);
+
+/// Index of a frame, the exit status of the gnuplot that rendered it and the
+/// wall time it took
+type FrameResult = (usize, Result<Option<i32>, ViewerError>, Duration);
+
+/// Settings shared by the gnuplot scripts of all frames
+#[derive(Clone)]
+struct PlotSettings {
+    directory: PathBuf,
+    /// Directory the frame images are written to
+    frames_directory: PathBuf,
+    /// Size option of the terminal
+    size: String,
+    /// Factor the frames are rendered larger than the video
+    supersample: u32,
+    terminal: Terminal,
+    /// Point types cycled through, frame `i` uses `point_types[i % len]`
+    point_types: Vec<String>,
+    initial_rotation: f64,
+    rotation_speed: f64,
+    rotate_axis: RotateAxis,
+    elevation: f64,
+    elevation_speed: f64,
+    /// View scale of the first and the last sample, interpolated in between
+    zoom_start: f64,
+    zoom_end: f64,
+    sample_time: f64,
+    /// Index of the first sample file
+    sample_start: usize,
+    sample_number: usize,
+    /// Title of each frame, `notitle` if unset
+    title_format: Option<TitleFormat>,
+    /// Factor from seconds to the displayed time unit
+    time_scale: f64,
+    time_unit: String,
+    min_bounds: Vec<f64>,
+    max_bounds: Vec<f64>,
+    emit_scripts: bool,
+    /// 1-based data column points are colored by
+    color_column: Option<usize>,
+    color_range: Option<(f64, f64)>,
+    /// `set palette` command used when coloring by a column or density
+    palette: &'static str,
+    /// Number of cells along each axis of the density image, points are
+    /// plotted if unset
+    heatmap: Option<usize>,
+    /// Whether `set view equal xyz` is emitted, ignored if `aspect` is set
+    equal_axes: bool,
+    /// Relative axis lengths, one per dimension
+    aspect: Option<Vec<f64>>,
+    /// Whether 2D data is plotted with a constant z = 0 column, the bounds
+    /// having a third axis
+    pseudo_3d: bool,
+    /// Point size, gnuplot's default if unset
+    point_size: Option<f64>,
+    /// Gnuplot color of the canvas
+    background: Option<String>,
+    /// Whether the canvas is transparent, `background` is unset then
+    transparent: bool,
+    /// Gnuplot color of the points, unused when coloring by a column
+    point_color: Option<String>,
+    /// Randomized colors written before the data of points
+    color_jitter: Option<ColorJitter>,
+    /// 1-based coordinate columns of data files, the first ones if unset
+    columns: Option<Vec<usize>>,
+    /// Field separator of data files, whitespace if unset
+    separator: Option<char>,
+    /// Number of previous frames drawn as trails
+    trail_length: usize,
+    /// Executable run to render frames
+    gnuplot_path: String,
+    /// Extra command line arguments of gnuplot
+    gnuplot_args: Vec<String>,
+    /// Whether data is written into the script after the plot command
+    /// instead of read from the files by gnuplot
+    inline_data: bool,
+    /// Number of inlined points kept of each sample file
+    max_points: Option<usize>,
+    /// Stride of the `every` modifier, gnuplot plots every Nth point then
+    every: Option<usize>,
+    /// Single file plotted instead of `{i}.dat`, holding the samples as blocks
+    /// separated by two blank lines
+    indexed_file: Option<PathBuf>,
+    /// Species plotted instead of `{i}.dat` if not empty
+    species: Vec<Species>,
+    /// Data file and time of each frame read by `--stdin-manifest`, replacing
+    /// `{i}.dat` and the sample time
+    records: Option<stdin_manifest::Records>,
+    grid: bool,
+    border: bool,
+    /// Labels of the x, y and z axes in order, possibly fewer
+    axis_labels: Vec<String>,
+    /// Axes like `xz` drawn in log scale
+    log_scale: Option<String>,
+    /// Keyframed camera path from `_camera.dat`, replacing the rotation speeds
+    camera: Option<CameraPath>,
+    /// Data file plotted in every frame
+    overlay: Option<PathBuf>,
+    /// Gnuplot style clause of the overlay
+    overlay_style: String,
+    /// Whether the color scale is drawn in every frame
+    colorbar: bool,
+    /// 1-based velocity columns drawn as arrows instead of points
+    vectors: Option<Vec<usize>>,
+    /// Factor of the arrow lengths
+    vector_scale: f64,
+    /// User commands written after the generated setup of every script
+    prelude: Option<String>,
+    /// Directories of `--mosaic` drawn next to this one in every frame
+    panels: Vec<PlotSettings>,
+    /// Position of the `--stats-overlay` label, and whether it is in a bottom
+    /// corner and grows upward
+    stats_overlay: Option<(&'static str, bool)>,
+    /// Centroid of each frame the bounds are moved to by `--follow-com`
+    centers: HashMap<usize, Vec<f64>>,
+    /// Bounds of each frame replacing `min_bounds` and `max_bounds` with
+    /// `--smooth-bounds`
+    frame_bounds: HashMap<usize, Bounds>,
+}
+
+/// Options of one render, one field per command line flag
+///
+/// `RenderOptions::default()` holds the defaults of the flags, so programs
+/// embedding the renderer only set the fields they change. `from_matches`
+/// reads them from the command line.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Data directory holding the sample files
+    pub path: PathBuf,
+    pub stage: Stage,
+    /// Sizes of the videos, frames are rendered at the largest one
+    pub sizes: Vec<(f64, f64)>,
+    /// Factor png frames are rendered larger than the video
+    pub supersample: u32,
+    pub frame_rate: f64,
+    /// Video length in seconds, replacing `frame_rate` if set
+    pub duration: Option<f64>,
+    pub terminal: Terminal,
+    pub format: VideoFormat,
+    /// Video file name without extension, relative to `path` or absolute
+    pub output_name: String,
+    /// Whether `_frames.json` is written
+    pub manifest: bool,
+    /// Whether render parameters are written next to the video
+    pub sidecar: bool,
+    /// Whether gnuplot renders an animated gif without ffmpeg
+    pub gif_direct: bool,
+    pub crf: Option<u32>,
+    pub bitrate: Option<String>,
+    pub two_pass: bool,
+    /// Point types cycled through by frame
+    pub point_types: Vec<String>,
+    pub point_size: Option<f64>,
+    pub workers: usize,
+    pub gnuplot_path: String,
+    pub ffmpeg_path: String,
+    /// Frame printed as text instead of rendering
+    pub ascii_preview: Option<usize>,
+    /// Whether the frames of `Stage::Preview` are opened
+    pub open_preview: bool,
+    pub inline_data: bool,
+    pub max_points: Option<usize>,
+    pub every: Option<usize>,
+    pub gnuplot_args: Vec<String>,
+    pub ffmpeg_args: Vec<String>,
+    /// Megabytes the estimated memory of the gnuplot workers is capped to
+    pub max_memory: Option<f64>,
+    pub initial_rotation: f64,
+    /// Full turns over all samples, replacing `rotation_speed` if set
+    pub rotations: Option<f64>,
+    pub rotation_speed: f64,
+    pub rotate_axis: RotateAxis,
+    pub elevation: f64,
+    pub elevation_speed: f64,
+    pub zoom_start: f64,
+    pub zoom_end: f64,
+    /// Number of axes plotted, one per bounds column if unset
+    pub dimension: Option<usize>,
+    pub pseudo_3d: bool,
+    pub follow_com: bool,
+    pub com_smoothing: usize,
+    pub smooth_bounds: Option<usize>,
+    /// 1-based coordinate columns of data files
+    pub columns: Option<Vec<usize>>,
+    /// 1-based velocity columns drawn as arrows
+    pub vectors: Option<Vec<usize>>,
+    pub vector_scale: f64,
+    /// Field separator of data and bounds files, whitespace if unset
+    pub separator: Option<char>,
+    pub species: Vec<Species>,
+    /// Min and max bounds replacing the ones of the data directory
+    pub bounds: Option<Bounds>,
+    pub auto_bounds: bool,
+    pub recompute_bounds: bool,
+    pub fit_bounds_to_frame: Option<usize>,
+    pub bounds_padding: f64,
+    pub color_column: Option<usize>,
+    /// Number of cells along each axis of the density image, points are
+    /// plotted if unset
+    pub heatmap: Option<usize>,
+    pub color_range: Option<(f64, f64)>,
+    pub colorbar: bool,
+    pub colorbar_image: bool,
+    pub fit_aspect: bool,
+    pub equal_axes: bool,
+    pub aspect: Option<Vec<f64>>,
+    /// Title of each frame, none if unset
+    pub title_format: Option<TitleFormat>,
+    pub time_scale: f64,
+    pub time_unit: String,
+    pub background: Option<String>,
+    pub transparent: bool,
+    pub point_color: Option<String>,
+    pub color_jitter: Option<ColorJitter>,
+    pub grid: bool,
+    pub border: bool,
+    pub axis_labels: Vec<String>,
+    /// Axes like `xz` drawn in log scale
+    pub log_scale: Option<String>,
+    /// Name of the palette, gnuplot's default if unset
+    pub palette: Option<String>,
+    /// File of gnuplot commands written after the generated setup
+    pub gnuplot_prelude: Option<PathBuf>,
+    pub overlay: Option<PathBuf>,
+    pub overlay_style: String,
+    pub indexed_file: Option<PathBuf>,
+    pub trail_length: usize,
+    pub sample_start: usize,
+    pub sample_step: usize,
+    pub use_concat: bool,
+    pub encode_segments: usize,
+    /// Whether `_poster.png` is written
+    pub poster: bool,
+    /// Frame of the poster, the middle one if unset
+    pub poster_frame: Option<usize>,
+    pub clock_overlay: Option<Corner>,
+    pub stats_overlay: Option<Corner>,
+    pub clean: bool,
+    pub frames_dir: Option<PathBuf>,
+    /// Directories drawn next to `path` in every frame
+    pub mosaic: Vec<PathBuf>,
+    pub check_consistency: Option<ConsistencyCheck>,
+    pub loop_mode: Option<LoopMode>,
+    pub frame_range: Option<(usize, usize)>,
+    pub since: Option<f64>,
+    pub until: Option<f64>,
+    pub dry_run: bool,
+    pub dry_run_all: bool,
+    /// Whether gnuplot and ffmpeg are checked to run before rendering
+    pub preflight: bool,
+    pub emit_scripts: bool,
+    /// Whether a progress bar is drawn on terminals
+    pub progress: bool,
+    pub max_retries: usize,
+    pub keep_going: bool,
+    pub resume: bool,
+    pub reuse_gnuplot: bool,
+    pub timings: bool,
+    pub watch: bool,
+    pub stdin_manifest: bool,
+    pub watch_timeout: Duration,
+    pub verbose: u64,
+    pub quiet: bool,
+    pub json_errors: bool,
+    pub color: ColorChoice,
+}
+
+/// Parts of a render that are run, chosen by the subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Frames and video, also without a subcommand
+    Render,
+    Frames,
+    Video,
+    /// The first and the last frame, chosen by `--preview`
+    Preview,
+}
+
+impl Stage {
+    fn makes_video(self) -> bool {
+        self == Stage::Render || self == Stage::Video
+    }
+}
+
+/// Corner of the video an overlay is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl FromStr for Corner {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Corner::TopLeft),
+            "top-right" => Ok(Corner::TopRight),
+            "bottom-left" => Ok(Corner::BottomLeft),
+            "bottom-right" => Ok(Corner::BottomRight),
+            _ => Err(ViewerError::Other(format!(
+                "unknown corner {:?}, expected one of top-left, top-right, bottom-left, \
+                 bottom-right",
+                s
+            ))),
+        }
+    }
+}
+
+/// What `--check-consistency` does at the first frame whose line count changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyCheck {
+    Warn,
+    Error,
+}
+
+impl FromStr for ConsistencyCheck {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(ConsistencyCheck::Warn),
+            "error" => Ok(ConsistencyCheck::Error),
+            _ => Err(ViewerError::Other(format!(
+                "unknown consistency check mode {:?}, expected one of warn, error",
+                s
+            ))),
+        }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions::from_args(["n-body-viewer"]).expect("defaults of the flags are valid")
+    }
+}
+
+impl RenderOptions {
+    /// Reads the options from the command line, merged with the `--config`
+    /// file and the `--quality` preset
+    ///
+    /// Values are parsed here, whether they fit together and to the data is
+    /// checked by `render`.
+    pub fn from_matches(matches: &ArgMatches) -> Result<RenderOptions, ViewerError> {
+        let mut config = match matches.value_of("config") {
+            Some(path) => Config::read(Path::new(path))?,
+            None => Config::default(),
+        };
+        if let Some(quality) = matches.value_of("quality") {
+            config.set_preset(quality_preset(quality)?);
+        }
+        let stage = match matches.subcommand_name() {
+            Some("video") if matches.is_present("no-video") => {
+                return Err(ViewerError::Other(
+                    "the video subcommand only assembles the video, --no-video leaves nothing \
+                     to do"
+                        .to_owned(),
+                ))
+            },
+            Some("video") if matches.is_present("preview") => {
+                return Err(ViewerError::Other(
+                    "the video subcommand renders no frames, --preview has nothing to show"
+                        .to_owned(),
+                ))
+            },
+            Some("video") => Stage::Video,
+            _ if matches.is_present("preview") => Stage::Preview,
+            Some("frames") => Stage::Frames,
+            _ if matches.is_present("no-video") => Stage::Frames,
+            _ => Stage::Render,
+        };
+        let terminal = match (
+            matches.value_of("terminal"),
+            matches.value_of("frame-format"),
+        ) {
+            (Some(terminal), _) => terminal.parse()?,
+            (None, Some("png")) | (None, None) => Terminal::Png,
+            (None, Some("jpeg")) => Terminal::Jpeg,
+            (None, Some(format)) => {
+                return Err(ViewerError::Other(format!(
+                    "unknown frame format {:?}, expected one of png, jpeg",
+                    format
+                )))
+            },
+        };
+        let transparent = matches.is_present("transparent");
+        let format = if matches.is_present("apng") {
+            VideoFormat::Apng
+        } else if transparent && matches.occurrences_of("format") == 0 {
+            VideoFormat::Mov
+        } else {
+            matches.value_of("format").unwrap().parse()?
+        };
+        let output_name =
+            if matches.is_present("apng") && matches.occurrences_of("output-name") == 0 {
+                "_animation"
+            } else {
+                matches.value_of("output-name").unwrap()
+            };
+        let bitrate = matches.value_of("bitrate");
+        let separator = match matches.value_of("separator") {
+            Some(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_whitespace() => None,
+                    (Some(c), None) => Some(c),
+                    _ => {
+                        return Err(ViewerError::Other(format!(
+                            "separator {:?} must be a single character",
+                            s
+                        )))
+                    },
+                }
+            },
+            None => None,
+        };
+        let bounds = match (
+            config.value_of(matches, "min-bounds"),
+            config.value_of(matches, "max-bounds"),
+        ) {
+            (Some(min_bounds), Some(max_bounds)) => Some((
+                read_bounds(min_bounds, separator)?,
+                read_bounds(max_bounds, separator)?,
+            )),
+            (None, None) => None,
+            _ => {
+                return Err(ViewerError::Other(
+                    "min bounds and max bounds must be set together".to_owned(),
+                ))
+            },
+        };
+        let color_jitter = if matches.is_present("color-jitter") {
+            Some(ColorJitter {
+                amount: matches.value_of("color-jitter").unwrap_or("24").parse()?,
+                seed: matches.value_of("seed").unwrap().parse()?,
+                id_column: matches.value_of("id-column").map(str::parse).transpose()?,
+            })
+        } else {
+            None
+        };
+        let strings = |name: &str| {
+            matches
+                .values_of(name)
+                .map_or_else(Vec::new, |values| values.map(str::to_owned).collect())
+        };
+        Ok(RenderOptions {
+            path: PathBuf::from(matches.value_of("path").unwrap()),
+            stage,
+            sizes: read_sizes(config.value_of(matches, "size").unwrap())?,
+            supersample: matches.value_of("supersample").unwrap().parse()?,
+            frame_rate: config.value_of(matches, "frame-rate").unwrap().parse()?,
+            duration: matches.value_of("duration").map(str::parse).transpose()?,
+            terminal,
+            format,
+            output_name: output_name.to_owned(),
+            manifest: matches.is_present("manifest"),
+            sidecar: matches.is_present("sidecar"),
+            gif_direct: matches.is_present("gif-direct"),
+            // a preset crf gives way to an explicit bitrate
+            crf: config
+                .value_of(matches, "crf")
+                .filter(|_| bitrate.is_none())
+                .map(str::parse)
+                .transpose()?,
+            bitrate: bitrate.map(str::to_owned),
+            two_pass: matches.is_present("two-pass"),
+            point_types: read_point_types(config.value_of(matches, "point-type").unwrap())?,
+            point_size: config
+                .value_of(matches, "point-size")
+                .map(str::parse)
+                .transpose()?,
+            workers: match config.value_of(matches, "worker") {
+                Some(w) => w.parse()?,
+                None => num_cpus::get(),
+            },
+            gnuplot_path: matches.value_of("gnuplot-path").unwrap().to_owned(),
+            ffmpeg_path: matches.value_of("ffmpeg-path").unwrap().to_owned(),
+            ascii_preview: matches
+                .value_of("ascii-preview")
+                .map(str::parse)
+                .transpose()?,
+            open_preview: !matches.is_present("no-open"),
+            inline_data: matches.is_present("inline-data"),
+            max_points: matches.value_of("max-points").map(str::parse).transpose()?,
+            every: matches.value_of("every").map(str::parse).transpose()?,
+            gnuplot_args: strings("gnuplot-arg"),
+            ffmpeg_args: strings("ffmpeg-arg"),
+            max_memory: matches.value_of("max-memory").map(str::parse).transpose()?,
+            initial_rotation: config
+                .value_of(matches, "initial-rotation")
+                .unwrap()
+                .parse()?,
+            rotations: matches.value_of("rotations").map(str::parse).transpose()?,
+            rotation_speed: config
+                .value_of(matches, "rotation-speed")
+                .unwrap()
+                .parse()?,
+            rotate_axis: matches.value_of("rotate-axis").unwrap().parse()?,
+            elevation: matches.value_of("elevation").unwrap().parse()?,
+            elevation_speed: matches.value_of("elevation-speed").unwrap().parse()?,
+            zoom_start: matches.value_of("zoom-start").unwrap().parse()?,
+            zoom_end: matches.value_of("zoom-end").unwrap().parse()?,
+            dimension: matches.value_of("dimension").map(str::parse).transpose()?,
+            pseudo_3d: matches.is_present("pseudo-3d"),
+            follow_com: matches.is_present("follow-com"),
+            com_smoothing: matches.value_of("com-smoothing").unwrap().parse()?,
+            smooth_bounds: matches
+                .value_of("smooth-bounds")
+                .map(str::parse)
+                .transpose()?,
+            columns: matches.value_of("columns").map(read_columns).transpose()?,
+            vectors: matches.value_of("vectors").map(read_columns).transpose()?,
+            vector_scale: matches.value_of("vector-scale").unwrap().parse()?,
+            separator,
+            species: matches.values_of("species").map_or_else(
+                || Ok(Vec::new()),
+                |species| species.map(str::parse).collect(),
+            )?,
+            bounds,
+            auto_bounds: matches.is_present("auto-bounds"),
+            recompute_bounds: matches.is_present("recompute-bounds"),
+            fit_bounds_to_frame: matches
+                .value_of("fit-bounds-to-frame")
+                .map(str::parse)
+                .transpose()?,
+            bounds_padding: matches.value_of("bounds-padding").unwrap().parse()?,
+            color_column: matches
+                .value_of("color-column")
+                .map(str::parse)
+                .transpose()?,
+            heatmap: if matches.is_present("heatmap") {
+                Some(matches.value_of("heatmap-bins").unwrap().parse()?)
+            } else {
+                None
+            },
+            color_range: matches.value_of("color-range").map(read_pair).transpose()?,
+            colorbar: matches.is_present("colorbar"),
+            colorbar_image: matches.is_present("colorbar-image"),
+            fit_aspect: matches.is_present("fit-aspect"),
+            equal_axes: !matches.is_present("no-equal-axes"),
+            aspect: matches.value_of("aspect").map(read_aspect).transpose()?,
+            title_format: if matches.is_present("no-title") {
+                None
+            } else {
+                Some(matches.value_of("title-format").unwrap().parse()?)
+            },
+            time_scale: matches.value_of("time-scale").unwrap().parse()?,
+            time_unit: matches.value_of("time-unit").unwrap().to_owned(),
+            background: matches.value_of("background").map(str::to_owned),
+            transparent,
+            point_color: matches.value_of("point-color").map(str::to_owned),
+            color_jitter,
+            grid: matches.is_present("grid") && !matches.is_present("no-grid"),
+            border: !matches.is_present("no-border"),
+            axis_labels: match matches.value_of("axis-labels") {
+                Some(labels) => labels.split(',').map(|l| l.trim().to_owned()).collect(),
+                None => Vec::new(),
+            },
+            log_scale: matches.value_of("log-scale").map(str::to_owned),
+            palette: matches.value_of("palette").map(str::to_owned),
+            gnuplot_prelude: matches.value_of("gnuplot-prelude").map(PathBuf::from),
+            overlay: matches.value_of("overlay").map(PathBuf::from),
+            overlay_style: matches.value_of("overlay-style").unwrap().to_owned(),
+            indexed_file: matches.value_of("indexed-file").map(PathBuf::from),
+            trail_length: matches.value_of("trail-length").unwrap().parse()?,
+            sample_start: matches.value_of("sample-start").unwrap().parse()?,
+            sample_step: matches.value_of("sample-step").unwrap().parse()?,
+            use_concat: matches.is_present("use-concat"),
+            encode_segments: matches.value_of("encode-segments").unwrap().parse()?,
+            poster: matches.is_present("poster"),
+            poster_frame: matches.value_of("poster").map(str::parse).transpose()?,
+            clock_overlay: matches
+                .value_of("clock-overlay")
+                .map(str::parse)
+                .transpose()?,
+            stats_overlay: if matches.is_present("stats-overlay") {
+                Some(
+                    matches
+                        .value_of("stats-overlay")
+                        .unwrap_or("top-left")
+                        .parse()?,
+                )
+            } else {
+                None
+            },
+            clean: matches.is_present("clean"),
+            frames_dir: matches.value_of("frames-dir").map(PathBuf::from),
+            mosaic: matches.value_of("mosaic").map_or_else(Vec::new, |mosaic| {
+                mosaic.split(',').map(PathBuf::from).collect()
+            }),
+            check_consistency: matches
+                .value_of("check-consistency")
+                .map(str::parse)
+                .transpose()?,
+            loop_mode: matches.value_of("loop").map(str::parse).transpose()?,
+            frame_range: matches.value_of("frame-range").map(read_pair).transpose()?,
+            since: matches.value_of("since").map(str::parse).transpose()?,
+            until: matches.value_of("until").map(str::parse).transpose()?,
+            dry_run: matches.is_present("dry-run"),
+            dry_run_all: matches.is_present("dry-run-all"),
+            preflight: !matches.is_present("no-preflight"),
+            emit_scripts: matches.is_present("emit-scripts"),
+            progress: !matches.is_present("no-progress"),
+            max_retries: matches.value_of("max-retries").unwrap().parse()?,
+            keep_going: matches.is_present("keep-going"),
+            resume: matches.is_present("resume"),
+            reuse_gnuplot: matches.is_present("reuse-gnuplot"),
+            timings: matches.is_present("timings"),
+            watch: matches.is_present("watch"),
+            stdin_manifest: matches.is_present("stdin-manifest"),
+            watch_timeout: read_timeout(matches.value_of("watch-timeout").unwrap())?,
+            verbose: matches.occurrences_of("verbose"),
+            quiet: matches.is_present("quiet"),
+            json_errors: matches.is_present("json-errors"),
+            color: matches.value_of("color").unwrap().parse()?,
+        })
+    }
+
+    /// Parses `args` like the command line, the first one being the program
+    /// name, e.g. `["n-body-viewer", "output", "--frame-rate", "60"]`
+    pub fn from_args<I, T>(args: I) -> Result<RenderOptions, ViewerError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        RenderOptions::from_matches(&app().get_matches_from_safe(args)?)
+    }
+}
+
+/// The command line interface, whose flags are the options of a render
+pub fn app() -> App<'static, 'static> {
+    // shared by every subcommand, so flags may come before or after it
+    let args: Vec<Arg> = vec![
+        Arg::with_name("path")
+            .default_value("./n-body-output")
+            .help("Sets n body output path")
+            .takes_value(true),
+        Arg::with_name("config")
+            .long("config")
+            .short("c")
+            .help(
+                "Loads options from a TOML file, explicit flags take precedence over its \
+                 values",
+            )
+            .takes_value(true),
+        Arg::with_name("size")
+            .long("size")
+            .short("s")
+            .default_value("1920,1080")
+            .help(
+                "Sets video size as W,H, or N for a square, several sizes separated by ; or \
+                 written as WxH separated by , make one video each",
+            )
+            .takes_value(true),
+        Arg::with_name("supersample")
+            .long("supersample")
+            .default_value("1")
+            .help(
+                "Renders png frames at N times the size and downscales them to --size while \
+                 encoding",
+            )
+            .takes_value(true),
+        Arg::with_name("frame-rate")
+            .long("frame-rate")
+            .short("f")
+            .default_value("30")
+            .help("Sets frame rate of video")
+            .takes_value(true),
+        Arg::with_name("duration")
+            .long("duration")
+            .short("d")
+            .help("Sets video length in seconds, deriving the frame rate from it")
+            .takes_value(true)
+            .conflicts_with("frame-rate"),
+        Arg::with_name("terminal")
+            .long("terminal")
+            .help(
+                "Sets the frame file type (png, svg or pdf), no video is created from svg \
+                 or pdf frames",
+            )
+            .takes_value(true)
+            .conflicts_with("gif-direct"),
+        Arg::with_name("frame-format")
+            .long("frame-format")
+            .help(
+                "Sets the image type of frames encoded into the video (png or jpeg), jpeg \
+                 frames take less disk space but are lossy",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&["terminal", "gif-direct"]),
+        Arg::with_name("format")
+            .long("format")
+            .short("F")
+            .default_value("mp4")
+            .help("Sets video format (mp4, webm, mkv, gif, mov or apng), mov if --transparent")
+            .takes_value(true),
+        Arg::with_name("apng")
+            .long("apng")
+            .help("Assembles a lossless animated png named _animation.png by default")
+            .conflicts_with_all(&["format", "gif-direct"]),
+        Arg::with_name("output-name")
+            .long("output-name")
+            .short("o")
+            .default_value("_video")
+            .help("Sets video file name without extension, relative to path or absolute")
+            .takes_value(true),
+        Arg::with_name("manifest")
+            .long("manifest")
+            .help("Writes index, image path, time and view angles of all frames to _frames.json"),
+        Arg::with_name("sidecar")
+            .long("sidecar")
+            .help("Writes render parameters and bounds next to the video as JSON"),
+        Arg::with_name("gif-direct")
+            .long("gif-direct")
+            .help("Renders an animated _animation.gif with gnuplot only, without ffmpeg"),
+        Arg::with_name("quality")
+            .long("quality")
+            .help(
+                "Sets defaults of size, frame rate, point size and crf (draft, normal or \
+                 high), overridden by the individual options",
+            )
+            .takes_value(true),
+        Arg::with_name("crf")
+            .long("crf")
+            .help("Sets constant rate factor of video encoder (0-51)")
+            .takes_value(true)
+            .conflicts_with("bitrate"),
+        Arg::with_name("bitrate")
+            .long("bitrate")
+            .help("Sets bitrate of video encoder, like 4M")
+            .takes_value(true),
+        Arg::with_name("two-pass")
+            .long("two-pass")
+            .help(
+                "Encodes mp4 and webm videos in two passes, so their size follows --bitrate \
+                 closely",
+            )
+            .requires("bitrate"),
+        Arg::with_name("point-type")
+            .long("point-type")
+            .short("p")
+            .default_value("1")
+            .help("Sets point type of gnu plot, or a list like 1,2,3 cycled through by frame")
+            .takes_value(true),
+        Arg::with_name("point-size")
+            .long("point-size")
+            .help("Sets point size of gnu plot")
+            .takes_value(true),
+        Arg::with_name("worker")
+            .long("worker")
+            .short("w")
+            .help("Sets worker number")
+            .takes_value(true),
+        Arg::with_name("gnuplot-path")
+            .long("gnuplot-path")
+            .help("Sets the gnuplot executable")
+            .takes_value(true)
+            .default_value("gnuplot"),
+        Arg::with_name("ffmpeg-path")
+            .long("ffmpeg-path")
+            .help("Sets the ffmpeg executable")
+            .takes_value(true)
+            .default_value("ffmpeg"),
+        Arg::with_name("ascii-preview")
+            .long("ascii-preview")
+            .help("Prints the points of frame N as text in the terminal instead of rendering")
+            .takes_value(true),
+        Arg::with_name("preview")
+            .long("preview")
+            .help(
+                "Renders only the first and the last frame and opens them instead of \
+                 creating a video",
+            )
+            .conflicts_with_all(&["gif-direct", "watch", "ascii-preview"]),
+        Arg::with_name("no-video")
+            .long("no-video")
+            .help("Renders the frames without assembling a video, like the frames subcommand")
+            .conflicts_with_all(&["gif-direct", "preview"]),
+        Arg::with_name("no-open")
+            .long("no-open")
+            .help("Leaves the frames of --preview unopened")
+            .requires("preview"),
+        Arg::with_name("inline-data").long("inline-data").help(
+            "Reads the data files and pipes their points to gnuplot inline instead of \
+             letting gnuplot read the files",
+        ),
+        Arg::with_name("max-points")
+            .long("max-points")
+            .help(
+                "Plots at most N points of each sample file, picked the same way in every \
+                 frame, implies --inline-data",
+            )
+            .takes_value(true)
+            .conflicts_with("indexed-file"),
+        Arg::with_name("every")
+            .long("every")
+            .help(
+                "Lets gnuplot plot only every Nth point of each sample file, cheaper than \
+                 --max-points as the data is not read beforehand",
+            )
+            .takes_value(true)
+            .conflicts_with("heatmap"),
+        Arg::with_name("gnuplot-arg")
+            .long("gnuplot-arg")
+            .help(
+                "Passes ARG to every gnuplot process before any other argument, the script \
+                 is still read from stdin; repeatable",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .allow_hyphen_values(true),
+        Arg::with_name("ffmpeg-arg")
+            .long("ffmpeg-arg")
+            .help(
+                "Passes ARG to the ffmpeg command creating the video, after all generated \
+                 options and right before the output path; repeatable, in order",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .allow_hyphen_values(true),
+        Arg::with_name("max-memory")
+            .long("max-memory")
+            .help("Caps the workers so their estimated gnuplot memory fits into MB megabytes")
+            .takes_value(true),
+        Arg::with_name("initial-rotation")
+            .long("initial-rotation")
+            .help("Sets initial rotation degree")
+            .default_value("45")
+            .takes_value(true),
+        Arg::with_name("rotations")
+            .long("rotations")
+            .help("Sets the number of full turns over all samples, deriving the rotation speed")
+            .takes_value(true)
+            .conflicts_with("rotation-speed"),
+        Arg::with_name("rotation-speed")
+            .long("rotation-speed")
+            .help("Sets the rotation speed(degree per frame)")
+            .default_value("0.1")
+            .takes_value(true),
+        Arg::with_name("rotate-axis")
+            .long("rotate-axis")
+            .help(
+                "Sets the axis the view turns around at the rotation speed: z changes the \
+                 azimuth, x and y tumble the plot by changing the elevation",
+            )
+            .default_value("z")
+            .takes_value(true),
+        Arg::with_name("elevation")
+            .long("elevation")
+            .help("Sets initial elevation degree")
+            .default_value("60")
+            .takes_value(true),
+        Arg::with_name("elevation-speed")
+            .long("elevation-speed")
+            .help("Sets the elevation speed(degree per frame)")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("zoom-start")
+            .long("zoom-start")
+            .help("Sets the view scale of the first sample")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("zoom-end")
+            .long("zoom-end")
+            .help("Sets the view scale of the last sample, interpolated linearly in between")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("dimension")
+            .long("dimension")
+            .help("Plots the first 1, 2 or 3 axes instead of one per bounds column")
+            .takes_value(true),
+        Arg::with_name("pseudo-3d")
+            .long("pseudo-3d")
+            .help("Plots 2D data in the z = 0 plane of a rotating 3D view")
+            .conflicts_with_all(&["aspect", "follow-com", "mosaic", "ascii-preview"]),
+        Arg::with_name("follow-com")
+            .long("follow-com")
+            .help("Moves the bounds window of each frame onto the centroid of its points")
+            .conflicts_with("watch"),
+        Arg::with_name("com-smoothing")
+            .long("com-smoothing")
+            .help("Averages the followed centroid over N frames before and after each frame")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("smooth-bounds")
+            .long("smooth-bounds")
+            .help(
+                "Computes the --auto-bounds of every frame, or its --follow-com centroid, \
+                 averaged over N frames before and after it",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&["com-smoothing", "fit-bounds-to-frame", "pseudo-3d"]),
+        Arg::with_name("columns")
+            .long("columns")
+            .help("Sets 1-based coordinate columns of data files, like 2,3,4")
+            .takes_value(true),
+        Arg::with_name("vectors")
+            .long("vectors")
+            .help("Draws arrows from the 1-based velocity columns DX,DY[,DZ]")
+            .takes_value(true),
+        Arg::with_name("vector-scale")
+            .long("vector-scale")
+            .help("Sets the factor of the arrow lengths")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("separator")
+            .long("separator")
+            .help("Sets field separator of data and bounds files, whitespace by default")
+            .takes_value(true),
+        Arg::with_name("species")
+            .long("species")
+            .help(
+                "Plots {i}.NAME.dat instead of {i}.dat with the species' point type and \
+                 color, given as NAME:POINTTYPE:COLOR",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("min-bounds")
+            .long("min-bounds")
+            .help("Force set min bounds")
+            .takes_value(true)
+            .requires("max-bounds"),
+        Arg::with_name("max-bounds")
+            .long("max-bounds")
+            .help("Force set max bounds")
+            .takes_value(true)
+            .requires("min-bounds"),
+        Arg::with_name("auto-bounds").long("auto-bounds").help(
+            "Computes bounds from all sample files instead of reading _bounds.dat, cached in \
+                 _bounds_auto.dat",
+        ),
+        Arg::with_name("recompute-bounds")
+            .long("recompute-bounds")
+            .help("Rescans the samples for computed bounds even if _bounds_auto.dat is up to date"),
+        Arg::with_name("fit-bounds-to-frame")
+            .long("fit-bounds-to-frame")
+            .help("Computes bounds from the sample file of frame N only, padded like --auto-bounds")
+            .takes_value(true)
+            .conflicts_with_all(&["auto-bounds", "indexed-file"]),
+        Arg::with_name("bounds-padding")
+            .long("bounds-padding")
+            .help("Sets the fraction by which auto bounds are expanded on each side")
+            .default_value("0.05")
+            .takes_value(true),
+        Arg::with_name("color-column")
+            .long("color-column")
+            .help("Colors points by the given data column (1-based) using a palette")
+            .takes_value(true),
+        Arg::with_name("heatmap")
+            .long("heatmap")
+            .help("Plots 2D data as a density image of point counts instead of points")
+            .conflicts_with_all(&[
+                "overlay",
+                "vectors",
+                "color-column",
+                "pseudo-3d",
+                "indexed-file",
+                "follow-com",
+            ]),
+        Arg::with_name("heatmap-bins")
+            .long("heatmap-bins")
+            .help("Sets the number of heatmap cells along each axis")
+            .default_value("100")
+            .takes_value(true),
+        Arg::with_name("color-range")
+            .long("color-range")
+            .help("Sets MIN:MAX of the color palette, scanned from data if absent")
+            .takes_value(true)
+            .requires("color-column"),
+        Arg::with_name("colorbar")
+            .long("colorbar")
+            .help("Draws the color scale next to the points or the heatmap"),
+        Arg::with_name("colorbar-image")
+            .long("colorbar-image")
+            .help("Renders the color scale once to _colorbar.png")
+            .requires("color-column"),
+        Arg::with_name("fit-aspect")
+            .long("fit-aspect")
+            .help(
+                "Widens the x or y bounds of 2D data around their middle to the aspect \
+                 ratio of --size, so the data fills the video undistorted",
+            )
+            .conflicts_with_all(&["aspect", "log-scale", "pseudo-3d", "mosaic"]),
+        Arg::with_name("no-equal-axes")
+            .long("no-equal-axes")
+            .help("Scales each axis independently instead of equally"),
+        Arg::with_name("aspect")
+            .long("aspect")
+            .help("Sets axis length ratios X:Y for 2D or X:Y:Z for 3D (X equal to Y)")
+            .takes_value(true),
+        Arg::with_name("title-format")
+            .long("title-format")
+            .help("Sets title template with tokens {time}, {time:.N}, {unit}, {frame} and {total}")
+            .default_value(DEFAULT_TITLE_FORMAT)
+            .takes_value(true),
+        Arg::with_name("time-scale")
+            .long("time-scale")
+            .help("Sets the factor from seconds to the displayed time unit")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("time-unit")
+            .long("time-unit")
+            .help("Sets the label of the displayed time unit")
+            .default_value("s")
+            .takes_value(true),
+        Arg::with_name("no-title")
+            .long("no-title")
+            .help("Omits the title of frames"),
+        Arg::with_name("background")
+            .long("background")
+            .help("Sets background color, a gnuplot color name or #rrggbb")
+            .takes_value(true),
+        Arg::with_name("transparent")
+            .long("transparent")
+            .help("Renders frames with a transparent background, encoded as mov by default")
+            .conflicts_with("background"),
+        Arg::with_name("point-color")
+            .long("point-color")
+            .help("Sets point color, a gnuplot color name or #rrggbb")
+            .takes_value(true)
+            .conflicts_with("color-column"),
+        Arg::with_name("color-jitter")
+            .long("color-jitter")
+            .help(
+                "Changes each color channel of every point by up to AMOUNT of 255, 24 if \
+                 omitted, the same for a particle in every frame, implies --inline-data",
+            )
+            .takes_value(true)
+            .min_values(0)
+            .max_values(1)
+            .conflicts_with_all(&["color-column", "heatmap", "vectors", "indexed-file"]),
+        Arg::with_name("seed")
+            .long("seed")
+            .help("Sets the seed of the colors of --color-jitter")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("id-column")
+            .long("id-column")
+            .help(
+                "Identifies particles by the given data column (1-based), so --color-jitter \
+                 keeps their colors when the order of lines changes",
+            )
+            .takes_value(true)
+            .requires("color-jitter"),
+        Arg::with_name("grid")
+            .long("grid")
+            .help("Draws grid lines")
+            .overrides_with("no-grid"),
+        Arg::with_name("no-grid")
+            .long("no-grid")
+            .help("Draws no grid lines, the default")
+            .overrides_with("grid"),
+        Arg::with_name("no-border")
+            .long("no-border")
+            .help("Hides the border around the plot"),
+        Arg::with_name("axis-labels")
+            .long("axis-labels")
+            .help("Sets axis labels as \"X,Y,Z\"")
+            .takes_value(true),
+        Arg::with_name("log-scale")
+            .long("log-scale")
+            .help("Draws the given axes in log scale, e.g. xyz or xy")
+            .takes_value(true),
+        Arg::with_name("palette")
+            .long("palette")
+            .help(
+                "Sets palette used with --color-column or --heatmap (viridis, jet, hot or \
+                 grayscale)",
+            )
+            .takes_value(true),
+        Arg::with_name("gnuplot-prelude")
+            .long("gnuplot-prelude")
+            .help(
+                "Writes the gnuplot commands of a file into every script after the \
+                 generated setup, so they can override it",
+            )
+            .takes_value(true),
+        Arg::with_name("overlay")
+            .long("overlay")
+            .help("Draws a fixed data file below the points of every frame")
+            .takes_value(true),
+        Arg::with_name("indexed-file")
+            .long("indexed-file")
+            .help(
+                "Reads all frames from one file of blocks separated by two blank \
+                 lines, frame i being plotted with `index i`",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&[
+                "species",
+                "watch",
+                "auto-bounds",
+                "follow-com",
+                "check-consistency",
+                "inline-data",
+                "ascii-preview",
+                "mosaic",
+            ]),
+        Arg::with_name("overlay-style")
+            .long("overlay-style")
+            .help("Sets the gnuplot style of the overlay")
+            .default_value("with points pointtype 0 linecolor rgb 'gray'")
+            .takes_value(true),
+        Arg::with_name("trail-length")
+            .long("trail-length")
+            .help("Sets the number of previous frames drawn as fading trails")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("sample-start")
+            .long("sample-start")
+            .help("Sets index of the first sample file")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("sample-step")
+            .long("sample-step")
+            .help("Renders only every Nth sample")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("use-concat").long("use-concat").help(
+            "Passes the frames to ffmpeg as the list _frames.txt instead of %d.png, \
+                 implied by --sample-step, --frame-range, --resume and --keep-going",
+        ),
+        Arg::with_name("encode-segments")
+            .long("encode-segments")
+            .help("Encodes the video in K segments in parallel, then joins them")
+            .default_value("1")
+            .takes_value(true),
+        Arg::with_name("poster")
+            .long("poster")
+            .help(
+                "Writes frame N, the middle one if N is omitted, to _poster.png and \
+                 attaches it to mp4 videos as cover, e.g. --poster=10",
+            )
+            .takes_value(true)
+            .min_values(0)
+            .max_values(1)
+            .require_equals(true)
+            .conflicts_with("gif-direct"),
+        Arg::with_name("clock-overlay")
+            .long("clock-overlay")
+            .help(
+                "Stamps the simulation time into the video in the corner top-left, \
+                 top-right, bottom-left or bottom-right with ffmpeg",
+            )
+            .takes_value(true)
+            .conflicts_with("gif-direct"),
+        Arg::with_name("stats-overlay")
+            .long("stats-overlay")
+            .help(
+                "Labels every frame with its number of points and their extent, in the \
+                 corner top-left if omitted, top-right, bottom-left or bottom-right",
+            )
+            .takes_value(true)
+            .min_values(0)
+            .max_values(1)
+            .conflicts_with_all(&["mosaic", "indexed-file"]),
+        Arg::with_name("clean").long("clean").help(
+            "Removes the frame images and scripts rendered by this run once the video \
+                 is created",
+        ),
+        Arg::with_name("frames-dir")
+            .long("frames-dir")
+            .help("Writes the frame images to DIR, created if missing, instead of path")
+            .takes_value(true),
+        Arg::with_name("mosaic")
+            .long("mosaic")
+            .help(
+                "Draws the frames of directories DIR,... next to the ones of path in a grid, \
+                 each with its own samples and bounds",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&["gif-direct", "watch"]),
+        Arg::with_name("check-consistency")
+            .long("check-consistency")
+            .help(
+                "Counts the data lines of every frame before rendering and warns or errors \
+                 at the first frame whose count changes",
+            )
+            .takes_value(true)
+            .conflicts_with("watch"),
+        Arg::with_name("loop")
+            .long("loop")
+            .help(
+                "Loops the video: none, repeat or pingpong playing the frames forward \
+                 then backward, gif and apng videos are marked to play once or forever, \
+                 repeat needs one of them",
+            )
+            .takes_value(true)
+            .conflicts_with("gif-direct"),
+        Arg::with_name("frame-range")
+            .long("frame-range")
+            .help("Renders only frames START:END (inclusive)")
+            .takes_value(true),
+        Arg::with_name("since")
+            .long("since")
+            .help("Renders only frames at or after this time, in the units of the title")
+            .takes_value(true)
+            .conflicts_with("frame-range"),
+        Arg::with_name("until")
+            .long("until")
+            .help("Renders only frames at or before this time, in the units of the title")
+            .takes_value(true)
+            .conflicts_with("frame-range"),
+        Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("Prints the gnuplot script of the first frame and the ffmpeg command only"),
+        Arg::with_name("dry-run-all")
+            .long("dry-run-all")
+            .help("Like --dry-run, but prints the gnuplot scripts of all frames"),
+        Arg::with_name("no-preflight")
+            .long("no-preflight")
+            .help("Skips checking that gnuplot and ffmpeg run before rendering"),
+        Arg::with_name("emit-scripts")
+            .long("emit-scripts")
+            .help("Writes the gnuplot script of each frame to {i}.gp"),
+        Arg::with_name("no-progress")
+            .long("no-progress")
+            .help("Prints a line per finished frame instead of a progress bar"),
+        Arg::with_name("max-retries")
+            .long("max-retries")
+            .help("Sets how many times a frame whose gnuplot failed is rendered again")
+            .default_value("0")
+            .takes_value(true),
+        Arg::with_name("keep-going").long("keep-going").help(
+            "Keeps rendering when frames fail and reports them at the end, the video \
+                 leaves them out and is not created if none was rendered",
+        ),
+        Arg::with_name("resume")
+            .long("resume")
+            .help("Skips frames whose PNG is newer than their sample file"),
+        Arg::with_name("reuse-gnuplot")
+            .long("reuse-gnuplot")
+            .help("Keeps one gnuplot process per worker for all its frames"),
+        Arg::with_name("timings")
+            .long("timings")
+            .help("Writes the render time of each frame to _timings.csv"),
+        Arg::with_name("watch")
+            .long("watch")
+            .help(
+                "Renders frames while their sample files appear, until _done.txt is \
+                 written or the watch timeout passes",
+            )
+            .conflicts_with_all(&["reuse-gnuplot", "gif-direct", "auto-bounds"]),
+        Arg::with_name("stdin-manifest")
+            .long("stdin-manifest")
+            .help(
+                "Renders the frames listed on stdin as they arrive, one JSON record like \
+                 {\"index\": 3, \"path\": \"3.dat\", \"time\": 1.5} per line",
+            )
+            .conflicts_with_all(&[
+                "watch",
+                "resume",
+                "reuse-gnuplot",
+                "auto-bounds",
+                "fit-bounds-to-frame",
+                "follow-com",
+                "smooth-bounds",
+                "indexed-file",
+                "species",
+                "mosaic",
+                "trail-length",
+                "frame-range",
+                "since",
+                "until",
+                "check-consistency",
+                "poster",
+                "clock-overlay",
+                "preview",
+                "ascii-preview",
+                "gif-direct",
+                "dry-run",
+                "dry-run-all",
+            ]),
+        Arg::with_name("watch-timeout")
+            .long("watch-timeout")
+            .help("Sets the seconds without a new sample file after which watching stops")
+            .default_value("600")
+            .takes_value(true),
+        Arg::with_name("verbose")
+            .long("verbose")
+            .short("v")
+            .multiple(true)
+            .help("Logs more details, repeat for even more, overrides RUST_LOG"),
+        Arg::with_name("quiet")
+            .long("quiet")
+            .short("q")
+            .help("Logs errors only, overrides RUST_LOG")
+            .conflicts_with("verbose"),
+        Arg::with_name("json-errors")
+            .long("json-errors")
+            .help("Prints a failure as a JSON object of its kind, message and frame to stderr"),
+        Arg::with_name("color")
+            .long("color")
+            .help("Colors terminal output: auto, always or never, auto honors NO_COLOR")
+            .default_value("auto")
+            .takes_value(true),
+    ]
+    .into_iter()
+    .map(|arg| arg.global(true))
+    .collect();
+    app_from_crate!()
+        .after_help(
+            "EXIT CODES:\n    0      everything was rendered\n    1      rendering or encoding \
+             failed\n    2      some frames failed under --keep-going, the rest was \
+             rendered\n    130    interrupted by Ctrl-C",
+        )
+        .args(&args)
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("Renders the frames and assembles them into a video, the default"),
+        )
+        .subcommand(SubCommand::with_name("frames").about("Renders the frames only"))
+        .subcommand(
+            SubCommand::with_name("video").about("Assembles a video from frames rendered before"),
+        )
+}
+
+/// Sets up colored output from `--color` and the log level from `-v` and `-q`,
+/// for programs that do not configure logging themselves
+///
+/// A logger set up before is kept.
+pub fn init_logging(options: &RenderOptions) {
+    color::init(options.color);
+    init_logger(options.verbose, options.quiet);
+}
+
+/// A render once the options are checked against the data directory
+struct RenderPlan {
+    settings: sync::Arc<PlotSettings>,
+    samples: RangeInclusive<usize>,
+    first_frame: usize,
+    last_frame: usize,
+    /// Frames of the video in order, without the ones of missing samples
+    frame_list: Vec<usize>,
+    /// Missing samples whose frames are skipped under `--keep-going`
+    gaps: Vec<usize>,
+    /// Frame rate of the video, derived from the duration if one is set
+    frame_rate: f64,
+    /// Largest video size
+    size: (f64, f64),
+    /// Pixel size frames are rendered at
+    render_size: (f64, f64),
+}
+
+/// The ffmpeg commands assembling the rendered frames into the videos
+struct Encoding {
+    output_paths: Vec<PathBuf>,
+    /// One per output, scaling the frames to its size
+    encoders: Vec<Encoder>,
+    /// First pass of `--two-pass`, if any, and the command writing each output
+    ffmpeg_commands: Vec<(Option<Command>, Command)>,
+    use_concat: bool,
+    concat_list: PathBuf,
+    poster: Option<usize>,
+    poster_path: PathBuf,
+    encode_segments: usize,
+    comment: String,
+}
+
+/// Outcome of rendering the frames
+struct RenderedFrames {
+    /// Frames of the video, without the samples that never appeared
+    frame_list: Vec<usize>,
+    last_frame: usize,
+    /// Frames that failed under `--keep-going`
+    failed: Vec<usize>,
+    /// Frames rendered by this run, the only ones removed by `--clean`
+    rendered: Vec<usize>,
+}
+
+/// Renders the frames of the data directory of `options` and assembles them
+/// into a video, or runs only the part chosen by `options.stage`
+///
+/// Returns `ViewerError::Interrupted` after Ctrl-C once the frames in flight
+/// are done, and `ViewerError::PartialFailure` once the video is made without
+/// the frames that failed under `--keep-going`.
+pub fn render(options: &RenderOptions) -> Result<(), ViewerError> {
+    info!("{:?}", options);
+    check_options(options)?;
+    let directory = options.path.as_path();
+    if !directory.is_dir() {
+        return Err(ViewerError::Other(format!(
+            "{:?} is not a directory",
+            directory
+        )));
+    }
+    let plan = plan_render(options)?;
+    let settings = &plan.settings;
+    if let Some(frame) = options.ascii_preview {
+        if !plan.samples.contains(&frame) {
+            return Err(ViewerError::Other(format!(
+                "preview frame {} out of sample range {}:{}",
+                frame,
+                plan.samples.start(),
+                plan.samples.end()
+            )));
+        }
+        return print_ascii_preview(settings, frame);
+    }
+    let encoding = plan_encoding(options, &plan)?;
+    if options.dry_run || options.dry_run_all {
+        return print_dry_run(options, &plan, &encoding);
+    }
+
+    if options.preflight {
+        if options.stage != Stage::Video {
+            preflight(
+                &settings.gnuplot_path,
+                "--version",
+                "gnuplot",
+                "--gnuplot-path",
+            )?;
+        }
+        // direct GIF output and vector frames do not need ffmpeg
+        if !options.gif_direct && options.terminal.is_raster() && options.stage.makes_video() {
+            preflight(&options.ffmpeg_path, "-version", "ffmpeg", "--ffmpeg-path")?;
+        }
+    }
+
+    if options.colorbar_image && options.stage != Stage::Video {
+        let mut gnuplot = spawn_gnuplot(settings)?;
+        {
+            let gnuplot_stdin = gnuplot.stdin.as_mut().expect("failed to get piped stdin");
+            let mut writer = BufWriter::new(gnuplot_stdin);
+            write_colorbar_script(&mut writer, settings, plan.size.1)?;
+        }
+        let output = gnuplot.wait_with_output()?;
+        println!(
+            "colorbar creation child process exited with status {:?}",
+            output.status.code()
+        );
+    }
+
+    if let Some(delay) = gif_delay(options, &plan) {
+        let mut gnuplot = spawn_gnuplot(settings)?;
+        {
+            let gnuplot_stdin = gnuplot.stdin.as_mut().expect("failed to get piped stdin");
+            let mut writer = BufWriter::new(gnuplot_stdin);
+            write_gif_script(&mut writer, settings, &plan.frame_list, delay)?;
+        }
+        let output = gnuplot.wait_with_output()?;
+        println!(
+            "animation creation child process exited with status {:?}",
+            output.status.code()
+        );
+        return Ok(());
+    }
+
+    let pool = ThreadPool::new(worker_number(options, &plan));
+    let frames = render_frames(options, &plan, &pool)?;
+
+    if options.stage == Stage::Frames {
+        info!("video assembly skipped");
+        println!(
+            "frames left in {:?}, assemble them with the video subcommand",
+            settings.frames_directory
+        );
+        return failure_result(frames.failed);
+    }
+
+    if options.stage == Stage::Preview {
+        let previews: Vec<PathBuf> = frames
+            .frame_list
+            .iter()
+            .filter(|i| !frames.failed.contains(i))
+            .map(|&i| frame_path(settings, i))
+            .collect();
+        if !options.open_preview || !has_display() {
+            for path in &previews {
+                println!("preview frame {:?}", path);
+            }
+        } else {
+            open_files(&previews);
+        }
+        return failure_result(frames.failed);
+    }
+
+    if !options.terminal.is_raster() {
+        println!(
+            "frames left as {} files, no video is created from vector frames",
+            options.terminal.extension()
+        );
+        return failure_result(frames.failed);
+    }
+
+    encode_video(options, &plan, encoding, frames, &pool)
+}
+
+/// Checks the options that do not depend on the data directory
+fn check_options(options: &RenderOptions) -> Result<(), ViewerError> {
+    let stage = options.stage;
+    if stage != Stage::Render && options.gif_direct {
+        return Err(ViewerError::Other(
+            "--gif-direct renders the animation in one go, it needs the render subcommand"
+                .to_owned(),
+        ));
+    }
+    if stage == Stage::Video && options.watch {
+        return Err(ViewerError::Other(
+            "the video subcommand renders no frames, --watch has nothing to wait for".to_owned(),
+        ));
+    }
+    if stage == Stage::Video && options.stdin_manifest {
+        return Err(ViewerError::Other(
+            "the video subcommand renders no frames, --stdin-manifest has nothing to list"
+                .to_owned(),
+        ));
+    }
+    if options.sizes.is_empty() {
+        return Err(ViewerError::Other("no video size given".to_owned()));
+    }
+    for &(width, height) in &options.sizes {
+        if width % 2.0 != 0.0 || height % 2.0 != 0.0 {
+            warn!(
+                "size {}x{} is odd, some codecs like libx264 need even dimensions",
+                width, height
+            );
+        }
+    }
+    if options.sizes.len() > 1 && options.gif_direct {
+        return Err(ViewerError::Other(
+            "--gif-direct is not encoded by ffmpeg, it cannot make several sizes".to_owned(),
+        ));
+    }
+    if options.terminal == Terminal::Jpeg {
+        warn!("jpeg frames are lossy, text and thin lines may look blurred in the video");
+        if options.transparent {
+            return Err(ViewerError::Other(
+                "jpeg frames have no transparency, --transparent needs png frames".to_owned(),
+            ));
+        }
+    }
+    if options.supersample == 0 {
+        return Err(ViewerError::Other(
+            "supersample factor must be positive".to_owned(),
+        ));
+    }
+    if options.supersample > 1 && options.gif_direct {
+        return Err(ViewerError::Other(
+            "--gif-direct is not encoded by ffmpeg, --supersample cannot downscale it".to_owned(),
+        ));
+    }
+    if let Some(dimension) = options.dimension {
+        if !(1..=3).contains(&dimension) {
+            return Err(ViewerError::Other(format!(
+                "dimension {} is not 1, 2 or 3",
+                dimension
+            )));
+        }
+    }
+    if options.rotate_axis != RotateAxis::Z {
+        let defaults = RenderOptions::default();
+        let ignored = [
+            (
+                "initial-rotation",
+                options.initial_rotation != defaults.initial_rotation,
+            ),
+            (
+                "elevation-speed",
+                options.elevation_speed != defaults.elevation_speed,
+            ),
+        ];
+        let axis = if options.rotate_axis == RotateAxis::X {
+            "x"
+        } else {
+            "y"
+        };
+        for (name, _) in ignored.iter().filter(|(_, set)| *set) {
+            warn!(
+                "--{} is ignored since the view turns around the {} axis",
+                name, axis
+            );
+        }
+    }
+    if options.zoom_start <= 0.0 || options.zoom_end <= 0.0 {
+        return Err(ViewerError::Other(format!(
+            "zoom {}:{} must be positive",
+            options.zoom_start, options.zoom_end
+        )));
+    }
+    if options.transparent && options.format.alpha_pixel_format().is_none() {
+        warn!(
+            "{} videos cannot carry transparency, use --format mov, webm or mkv",
+            options.format.extension()
+        );
+    }
+    if let Some(crf) = options.crf {
+        if crf > 51 {
+            return Err(ViewerError::Other(format!(
+                "crf {} out of range, expected 0 to 51",
+                crf
+            )));
+        }
+    }
+    if options.color_column == Some(0) {
+        return Err(ViewerError::Other("color column is 1-based".to_owned()));
+    }
+    if let Some(point_size) = options.point_size {
+        if point_size <= 0.0 {
+            return Err(ViewerError::Other(format!(
+                "point size {} must be positive",
+                point_size
+            )));
+        }
+    }
+    if options.max_points == Some(0) {
+        return Err(ViewerError::Other("max points must be positive".to_owned()));
+    }
+    if options.every == Some(0) {
+        return Err(ViewerError::Other("every must be positive".to_owned()));
+    }
+    if let Some(ref file) = options.indexed_file {
+        if !file.is_file() {
+            return Err(ViewerError::Other(format!(
+                "indexed file {:?} is not a file",
+                file
+            )));
+        }
+    }
+    if let Some(ref overlay) = options.overlay {
+        if !overlay.is_file() {
+            return Err(ViewerError::Other(format!(
+                "overlay {:?} is not a file",
+                overlay
+            )));
+        }
+    }
+    if options.axis_labels.len() > 3 {
+        return Err(ViewerError::Other(format!(
+            "{} axis labels given, at most 3 axes are labeled",
+            options.axis_labels.len()
+        )));
+    }
+    if let Some(ref color_jitter) = options.color_jitter {
+        if color_jitter.id_column == Some(0) {
+            return Err(ViewerError::Other("id column is 1-based".to_owned()));
+        }
+        // colors are jittered numerically, gnuplot color names are unknown
+        let colors = options
+            .species
+            .iter()
+            .map(|s| s.color.as_str())
+            .chain(options.point_color.as_deref());
+        for color in colors {
+            if jitter::parse_rgb(color).is_none() {
+                return Err(ViewerError::Other(format!(
+                    "--color-jitter needs #rrggbb colors, {:?} is not one",
+                    color
+                )));
+            }
+        }
+    }
+    if options.loop_mode == Some(LoopMode::Repeat) && !options.format.is_animation() {
+        return Err(ViewerError::Other(format!(
+            "--loop repeat marks gif and apng animations to play forever, {} videos cannot \
+             loop",
+            options.format.extension()
+        )));
+    }
+    if options.sample_step == 0 {
+        return Err(ViewerError::Other(
+            "sample step must be positive".to_owned(),
+        ));
+    }
+    if options.heatmap == Some(0) {
+        return Err(ViewerError::Other(
+            "heatmap bins must be positive".to_owned(),
+        ));
+    }
+    if let Some(duration) = options.duration {
+        if duration <= 0.0 {
+            return Err(ViewerError::Other(format!(
+                "duration {} must be positive",
+                duration
+            )));
+        }
+    }
+    if options.smooth_bounds.is_some() && !options.follow_com && !options.auto_bounds {
+        return Err(ViewerError::Other(
+            "--smooth-bounds needs --auto-bounds or --follow-com".to_owned(),
+        ));
+    }
+    let colored = options.color_column.is_some() || options.heatmap.is_some();
+    if options.palette.is_some() && !colored {
+        return Err(ViewerError::Other(
+            "--palette needs --color-column or --heatmap".to_owned(),
+        ));
+    }
+    if options.colorbar && !colored {
+        return Err(ViewerError::Other(
+            "--colorbar needs --color-column or --heatmap".to_owned(),
+        ));
+    }
+    if options.color_column.is_some() && options.color_range.is_none() && options.watch {
+        return Err(ViewerError::Other(
+            "--watch needs --color-range since not all samples exist yet".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the samples and bounds of the data directory and sets up the plot
+/// settings and the frames of the render
+fn plan_render(options: &RenderOptions) -> Result<RenderPlan, ViewerError> {
+    let directory = options.path.as_path();
+    let stage = options.stage;
+    // frames are rendered once at the largest size, ffmpeg scales the others
+    let size = options
+        .sizes
+        .iter()
+        .cloned()
+        .fold((0.0, 0.0), |largest, size| {
+            if size.0 * size.1 > largest.0 * largest.1 {
+                size
+            } else {
+                largest
+            }
+        });
+    let supersample = if options.supersample > 1 && options.terminal != Terminal::Png {
+        warn!(
+            "--supersample needs png frames, it is ignored for {} frames",
+            options.terminal.extension()
+        );
+        1
+    } else {
+        options.supersample
+    };
+    let render_size = (size.0 * supersample as f64, size.1 * supersample as f64);
+    let palette = match options.palette {
+        Some(ref name) => palette_definition(name)?,
+        None => "set palette",
+    };
+    let prelude = match options.gnuplot_prelude {
+        Some(ref path) => Some(fs::read_to_string(path)?),
+        None => None,
+    };
+    let stdin_manifest = options.stdin_manifest;
+    let has_meta = ["_meta.json", "_sample.txt"]
+        .iter()
+        .any(|name| directory.join(name).is_file());
+    let Meta {
+        sample_number,
+        sample_time,
+        bounds: meta_bounds,
+    } = if stdin_manifest && !has_meta {
+        // frames are listed on stdin, times default to their index
+        Meta {
+            sample_number: 0,
+            sample_time: 1.0,
+            bounds: None,
+        }
+    } else {
+        read_samples(directory)?
+    };
+    info!("sample number: {}", sample_number);
+    let sample_start = options.sample_start;
+    let samples = sample_start..=sample_start + sample_number;
+    info!("sample time: {} s", sample_time);
+    let rotation_speed = match options.rotations {
+        Some(rotations) => {
+            let rotation_speed = if sample_number == 0 {
+                0.0
+            } else {
+                rotations * 360.0 / sample_number as f64
+            };
+            info!(
+                "{} rotations: rotation speed {} degrees per frame",
+                rotations, rotation_speed
+            );
+            rotation_speed
+        },
+        None => options.rotation_speed,
+    };
+    let fit_frame = options.fit_bounds_to_frame;
+    if let Some(frame) = fit_frame {
+        if !samples.contains(&frame) {
+            return Err(ViewerError::Other(format!(
+                "bounds frame {} out of sample range {}:{}",
+                frame,
+                samples.start(),
+                samples.end()
+            )));
+        }
+        info!("bounds fitted to frame {}", frame);
+    }
+    // samples are found by listing the directory once, watching waits for
+    // them instead
+    let gaps = if options.watch || stdin_manifest || options.indexed_file.is_some() {
+        Vec::new()
+    } else {
+        find_missing_samples(directory, &samples, &options.species)?
+    };
+    if !gaps.is_empty() {
+        if options.keep_going {
+            warn!(
+                "samples {} are missing, their frames are skipped",
+                format_ranges(&gaps)
+            );
+        } else {
+            return Err(ViewerError::Other(format!(
+                "samples {} are missing in {:?}, pass --keep-going to skip their frames",
+                format_ranges(&gaps),
+                directory
+            )));
+        }
+    }
+    let fit_ratio = if options.fit_aspect {
+        Some(size.0 / size.1)
+    } else {
+        None
+    };
+    let ((min_bounds, max_bounds), dimension) =
+        plot_bounds(options, &samples, meta_bounds, fit_ratio)?;
+    if let Some(ref columns) = options.columns {
+        if columns.len() != dimension {
+            return Err(ViewerError::Other(format!(
+                "{} columns given for {}D data",
+                columns.len(),
+                dimension
+            )));
+        }
+    }
+    if let Some(ref vectors) = options.vectors {
+        if dimension == 1 || vectors.len() != dimension {
+            return Err(ViewerError::Other(format!(
+                "{} vector columns given for {}D data, expected DX,DY or DX,DY,DZ",
+                vectors.len(),
+                dimension
+            )));
+        }
+    }
+    if let Some(ref aspect) = options.aspect {
+        check_aspect(aspect, dimension)?;
+    }
+    if options.heatmap.is_some() {
+        if dimension != 2 {
+            return Err(ViewerError::Other(format!(
+                "--heatmap needs 2D data, got {}D",
+                dimension
+            )));
+        }
+        if options.trail_length > 0 {
+            warn!("--trail-length is ignored by --heatmap");
+        }
+    }
+    if let Some(ref axes) = options.log_scale {
+        check_log_scale(axes, &min_bounds)?;
+    }
+
+    let camera_path = directory.join("_camera.dat");
+    let camera = if camera_path.exists() {
+        info!("camera path: {:?}", camera_path);
+        let defaults = RenderOptions::default();
+        if options.zoom_start != defaults.zoom_start || options.zoom_end != defaults.zoom_end {
+            warn!("--zoom-start and --zoom-end are ignored since a camera path is used");
+        }
+        Some(read_camera(&camera_path, &samples, options.separator)?)
+    } else {
+        None
+    };
+
+    let (first_frame, last_frame) = match options.frame_range {
+        Some(range) => check_frame_range(range, &samples)?,
+        None if options.since.is_some() || options.until.is_some() => read_time_window(
+            options.since,
+            options.until,
+            sample_time * options.time_scale,
+            &samples,
+        )?,
+        None => (*samples.start(), *samples.end()),
+    };
+    info!("frame range: {}:{}", first_frame, last_frame);
+    let mut frame_list: Vec<usize> = (first_frame..=last_frame)
+        .step_by(options.sample_step)
+        .collect();
+    if stage == Stage::Preview {
+        frame_list = vec![first_frame, last_frame];
+        frame_list.dedup();
+    }
+    frame_list.retain(|i| gaps.binary_search(i).is_err());
+    if frame_list.is_empty() {
+        return Err(ViewerError::Other(format!(
+            "samples of frames {}:{} are all missing",
+            first_frame, last_frame
+        )));
+    }
+    if let Some(mode) = options.check_consistency {
+        info!("checking data line counts of {} frames", frame_list.len());
+        if let Some(message) = find_inconsistent_frame(directory, &frame_list, &options.species)? {
+            if mode == ConsistencyCheck::Error {
+                return Err(ViewerError::Other(message));
+            }
+            warn!("{}, the file may be truncated", message);
+        }
+    }
+    let frame_rate = match options.duration {
+        Some(duration) => {
+            let frame_number = frame_list.len();
+            let frame_rate = (frame_number as f64 / duration * 1000.0).round() / 1000.0;
+            info!(
+                "frame rate: {} fps for {} frames in {} s",
+                frame_rate, frame_number, duration
+            );
+            frame_rate
+        },
+        None => options.frame_rate,
+    };
+    let color_range = match (options.color_column, options.color_range) {
+        (None, _) => None,
+        (Some(_), Some(range)) => Some(range),
+        (Some(column), None) => Some(scan_column_range(
+            directory,
+            first_frame..=last_frame,
+            &options.species,
+            column,
+            options.separator,
+        )?),
+    };
+    if let Some((min, max)) = color_range {
+        info!("color range: {}:{}", min, max);
+    }
+    let follow_com = if options.follow_com {
+        Some(options.smooth_bounds.unwrap_or(options.com_smoothing))
+    } else {
+        None
+    };
+    // frames move with their centroid instead if following it
+    let frame_window = options
+        .smooth_bounds
+        .filter(|_| follow_com.is_none() && options.bounds.is_none());
+    let center_columns = options
+        .columns
+        .clone()
+        .unwrap_or_else(|| (1..=dimension).collect());
+    let centers = match follow_com {
+        Some(smoothing) => compute_centers(
+            directory,
+            &frame_list,
+            &options.species,
+            &center_columns,
+            options.separator,
+            smoothing,
+        )?,
+        None => HashMap::new(),
+    };
+    let frame_bounds = match frame_window {
+        Some(window) => compute_frame_bounds(
+            directory,
+            &frame_list,
+            &options.species,
+            options.bounds_padding,
+            &center_columns,
+            options.separator,
+            window,
+        )?
+        .into_iter()
+        .map(|(i, bounds)| match fit_ratio {
+            Some(ratio) => (i, fit_aspect(bounds, ratio)),
+            None => (i, bounds),
+        })
+        .collect(),
+        None => HashMap::new(),
+    };
+    let frames_directory = match options.frames_dir {
+        Some(ref frames_directory) => {
+            if !options.dry_run && !options.dry_run_all {
+                fs::create_dir_all(frames_directory)?;
+            }
+            frames_directory.clone()
+        },
+        None => directory.to_owned(),
+    };
+    let mut settings = PlotSettings {
+        directory: directory.to_owned(),
+        frames_directory,
+        size: options.terminal.size(render_size),
+        supersample,
+        terminal: options.terminal,
+        point_types: options.point_types.clone(),
+        initial_rotation: options.initial_rotation,
+        rotation_speed,
+        rotate_axis: options.rotate_axis,
+        elevation: options.elevation,
+        elevation_speed: options.elevation_speed,
+        zoom_start: options.zoom_start,
+        zoom_end: options.zoom_end,
+        sample_time,
+        sample_start,
+        sample_number,
+        title_format: options.title_format.clone(),
+        time_scale: options.time_scale,
+        time_unit: options.time_unit.clone(),
+        min_bounds,
+        max_bounds,
+        emit_scripts: options.emit_scripts,
+        color_column: options.color_column,
+        color_range,
+        palette,
+        heatmap: options.heatmap,
+        trail_length: options.trail_length,
+        columns: options.columns.clone(),
+        separator: options.separator,
+        point_size: options.point_size,
+        background: options.background.clone(),
+        transparent: options.transparent,
+        point_color: options.point_color.clone(),
+        color_jitter: options.color_jitter.clone(),
+        equal_axes: options.equal_axes,
+        aspect: options.aspect.clone(),
+        pseudo_3d: options.pseudo_3d,
+        gnuplot_path: options.gnuplot_path.clone(),
+        inline_data: options.inline_data
+            || options.max_points.is_some()
+            || options.color_jitter.is_some(),
+        max_points: options.max_points,
+        every: options.every,
+        indexed_file: options.indexed_file.clone(),
+        records: stdin_manifest.then(Default::default),
+        gnuplot_args: options.gnuplot_args.clone(),
+        species: options.species.clone(),
+        grid: options.grid,
+        border: options.border,
+        axis_labels: options.axis_labels.clone(),
+        log_scale: options.log_scale.clone(),
+        camera,
+        overlay: options.overlay.clone(),
+        overlay_style: options.overlay_style.clone(),
+        colorbar: options.colorbar,
+        vectors: options.vectors.clone(),
+        vector_scale: options.vector_scale,
+        prelude,
+        panels: Vec::new(),
+        stats_overlay: options.stats_overlay.map(stats_position),
+        centers,
+        frame_bounds,
+    };
+    let auto_bounds =
+        Some(options.bounds_padding).filter(|_| options.auto_bounds || fit_frame.is_some());
+    for panel_directory in &options.mosaic {
+        if !panel_directory.is_dir() {
+            return Err(ViewerError::Other(format!(
+                "mosaic panel {:?} is not a directory",
+                panel_directory
+            )));
+        }
+        let mut panel = panel_settings(
+            &settings,
+            panel_directory,
+            options.bounds.is_some(),
+            auto_bounds,
+            fit_frame,
+            options.recompute_bounds,
+            options.dimension,
+        )?;
+        if panel.sample_start + panel.sample_number < last_frame {
+            return Err(ViewerError::Other(format!(
+                "mosaic panel {:?} has {} samples, too few for frame {}",
+                panel_directory, panel.sample_number, last_frame
+            )));
+        }
+        info!(
+            "mosaic panel {:?}: {} samples, bounds {:?} {:?}",
+            panel_directory, panel.sample_number, panel.min_bounds, panel.max_bounds
+        );
+        if let Some(smoothing) = follow_com {
+            panel.centers = compute_centers(
+                panel_directory,
+                &frame_list,
+                &panel.species,
+                &center_columns,
+                options.separator,
+                smoothing,
+            )?;
+        }
+        if let Some(window) = frame_window {
+            panel.frame_bounds = compute_frame_bounds(
+                panel_directory,
+                &frame_list,
+                &panel.species,
+                options.bounds_padding,
+                &center_columns,
+                options.separator,
+                window,
+            )?;
+        }
+        settings.panels.push(panel);
+    }
+    Ok(RenderPlan {
+        settings: sync::Arc::new(settings),
+        samples,
+        first_frame,
+        last_frame,
+        frame_list,
+        gaps,
+        frame_rate,
+        size,
+        render_size,
+    })
+}
+
+/// Bounds of the plot and the number of data axes, set by the options or
+/// computed or read from the data directory
+///
+/// The bounds get a thin third axis for `--pseudo-3d` and are widened for
+/// `--fit-aspect` to the aspect ratio `fit_ratio`.
+fn plot_bounds(
+    options: &RenderOptions,
+    samples: &RangeInclusive<usize>,
+    meta_bounds: Option<Bounds>,
+    fit_ratio: Option<f64>,
+) -> Result<(Bounds, usize), ViewerError> {
+    let directory = options.path.as_path();
+    let separator = options.separator;
+    let fit_frame = options.fit_bounds_to_frame;
+    let (source, origin, (min_bounds, max_bounds)) = if let Some(ref bounds) = options.bounds {
+        if options.auto_bounds || fit_frame.is_some() {
+            warn!("computing bounds is skipped since --min-bounds and --max-bounds are set");
+        }
+        (
+            "set by --min-bounds and --max-bounds",
+            "--min-bounds and --max-bounds".to_owned(),
+            bounds.clone(),
+        )
+    } else if options.auto_bounds || fit_frame.is_some() {
+        let columns = bounds_columns(
+            directory,
+            options.columns.as_deref(),
+            options.dimension,
+            meta_bounds.as_ref(),
+            separator,
+        )?;
+        // a single frame is scanned quickly, it would only evict the cached
+        // bounds of all samples
+        let bounds = match fit_frame {
+            Some(frame) => compute_bounds(
+                directory,
+                frame..=frame,
+                &options.species,
+                options.bounds_padding,
+                separator,
+                &columns,
+            )?,
+            None => cached_bounds(
+                directory,
+                samples.clone(),
+                &options.species,
+                options.bounds_padding,
+                separator,
+                &columns,
+                options.recompute_bounds,
+            )?,
+        };
+        (
+            "computed from the samples",
+            format!("the samples of {:?}", directory),
+            bounds,
+        )
+    } else if let Some(bounds) = meta_bounds {
+        (
+            "read from _meta.json",
+            format!("{:?}", directory.join("_meta.json")),
+            bounds,
+        )
+    } else {
+        (
+            "read from _bounds.dat",
+            format!("{:?}", directory.join("_bounds.dat")),
+            read_bounds_file(directory, separator)?,
+        )
+    };
+    let (min_bounds, max_bounds) =
+        select_dimension(&origin, min_bounds, max_bounds, options.dimension)?;
+    log_bounds(directory, source, &min_bounds, &max_bounds);
+    let dimension = max_bounds.len();
+    let (min_bounds, max_bounds) = if options.pseudo_3d {
+        if dimension != 2 {
+            return Err(ViewerError::Other(format!(
+                "--pseudo-3d needs 2D data, got {}D",
+                dimension
+            )));
+        }
+        // a thin slab around the plane keeps the tilt visible
+        let extent = (max_bounds[0] - min_bounds[0]).max(max_bounds[1] - min_bounds[1]);
+        let z = extent / 10.0;
+        (
+            vec![min_bounds[0], min_bounds[1], -z],
+            vec![max_bounds[0], max_bounds[1], z],
+        )
+    } else {
+        (min_bounds, max_bounds)
+    };
+    let bounds = match fit_ratio {
+        Some(ratio) => {
+            if dimension != 2 {
+                return Err(ViewerError::Other(format!(
+                    "--fit-aspect needs 2D data, got {}D",
+                    dimension
+                )));
+            }
+            let (min_bounds, max_bounds) = fit_aspect((min_bounds, max_bounds), ratio);
+            log_bounds(
+                directory,
+                "fitted to the aspect ratio of the video",
+                &min_bounds,
+                &max_bounds,
+            );
+            (min_bounds, max_bounds)
+        },
+        None => (min_bounds, max_bounds),
+    };
+    Ok((bounds, dimension))
+}
+
+/// Delay between the frames of `--gif-direct` in hundredths of a second
+fn gif_delay(options: &RenderOptions, plan: &RenderPlan) -> Option<u32> {
+    if options.gif_direct {
+        Some((100.0 / plan.frame_rate).round().max(1.0) as u32)
+    } else {
+        None
+    }
+}
+
+/// Builds the ffmpeg commands of the videos, one per size
+fn plan_encoding(options: &RenderOptions, plan: &RenderPlan) -> Result<Encoding, ViewerError> {
+    let settings = &plan.settings;
+    let directory = settings.directory.as_path();
+    let frames_directory = settings.frames_directory.as_path();
+    let format = options.format;
+    let (first_frame, last_frame) = (plan.first_frame, plan.last_frame);
+    // joining an absolute path replaces the directory
+    let output_paths: Vec<PathBuf> = if options.sizes.len() == 1 {
+        vec![directory.join(format!("{}.{}", options.output_name, format.extension()))]
+    } else {
+        options
+            .sizes
+            .iter()
+            .map(|(width, height)| {
+                directory.join(format!(
+                    "{}_{}x{}.{}",
+                    options.output_name,
+                    width,
+                    height,
+                    format.extension()
+                ))
+            })
+            .collect()
+    };
+    // the frames of a stdin manifest are only known once they are rendered
+    let frames_comment = if options.stdin_manifest {
+        "frames listed on stdin".to_owned()
+    } else {
+        format!("frames {}:{}", first_frame, last_frame)
+    };
+    let comment = format!(
+        "rendered by n-body-viewer: samples {}:{}, sample time {} s, {}, initial rotation {}, \
+         rotation speed {}",
+        plan.samples.start(),
+        plan.samples.end(),
+        settings.sample_time,
+        frames_comment,
+        settings.initial_rotation,
+        settings.rotation_speed
+    );
+    // `%d.png` reads from the first frame on until a number is missing, so
+    // steps, ranges and resumed renders list their frames for the concat
+    // demuxer instead, as do renders that may leave out failed frames, where
+    // the pattern would pick up stale frames of earlier runs
+    let loop_mode = options.loop_mode;
+    let use_concat = options.use_concat
+        || loop_mode == Some(LoopMode::PingPong)
+        || options.sample_step > 1
+        || options.frame_range.is_some()
+        || options.since.is_some()
+        || options.until.is_some()
+        || options.resume
+        || options.stdin_manifest
+        || options.keep_going
+        || !plan.gaps.is_empty();
+    let concat_list = frames_directory.join("_frames.txt");
+    let poster = if options.poster {
+        let frame = options
+            .poster_frame
+            .unwrap_or(plan.frame_list[plan.frame_list.len() / 2]);
+        if !plan.samples.contains(&frame) {
+            return Err(ViewerError::Other(format!(
+                "poster frame {} out of sample range {}:{}",
+                frame,
+                plan.samples.start(),
+                plan.samples.end()
+            )));
+        }
+        if options.terminal != Terminal::Png {
+            return Err(ViewerError::Other("--poster needs png frames".to_owned()));
+        }
+        Some(frame)
+    } else {
+        None
+    };
+    let poster_path = directory.join("_poster.png");
+    let encode_segments = if options.encode_segments > 1 && format.is_animation() {
+        warn!(
+            "{} animations are encoded in one piece, --encode-segments is ignored",
+            format.extension()
+        );
+        1
+    } else {
+        options.encode_segments.max(1)
+    };
+    let two_pass = options.two_pass;
+    if two_pass && !format.has_two_pass() {
+        return Err(ViewerError::Other(format!(
+            "--two-pass needs mp4 or webm videos, {} has no second pass",
+            format.extension()
+        )));
+    }
+    if two_pass && encode_segments > 1 {
+        return Err(ViewerError::Other(
+            "--two-pass encodes the whole video twice, it cannot be combined with \
+             --encode-segments"
+                .to_owned(),
+        ));
+    }
+    if encode_segments > 1 && options.sizes.len() > 1 {
+        return Err(ViewerError::Other(
+            "--encode-segments joins a single video, it cannot be combined with several sizes"
+                .to_owned(),
+        ));
+    }
+    let mut filters = Vec::new();
+    if let Some(corner) = options.clock_overlay {
+        if encode_segments > 1 || loop_mode == Some(LoopMode::PingPong) {
+            return Err(ViewerError::Other(
+                "--clock-overlay counts frames of a single forward encode, it cannot be \
+                 combined with --encode-segments or --loop pingpong"
+                    .to_owned(),
+            ));
+        }
+        // the clock grows by one step per video frame, a resumed render lists
+        // every frame again so only skipped or failed ones would skew it
+        if options.keep_going || options.stdin_manifest {
+            return Err(ViewerError::Other(
+                "--clock-overlay needs evenly spaced frames, it cannot be combined with \
+                 --keep-going, which skips missing or failed frames, or --stdin-manifest"
+                    .to_owned(),
+            ));
+        }
+        let time_step = settings.sample_time * settings.time_scale;
+        filters.push(clock_filter(
+            corner,
+            time_step * first_frame as f64,
+            time_step * options.sample_step as f64,
+            &settings.time_unit,
+        ));
+    }
+    let encoder = Encoder {
+        program: options.ffmpeg_path.clone(),
+        frame_rate: plan.frame_rate.to_string(),
+        format,
+        crf: options.crf,
+        bitrate: options.bitrate.clone(),
+        pixel_format: format.alpha_pixel_format().filter(|_| options.transparent),
+        filters: Vec::new(),
+        pass: None,
+    };
+    // one encoder per size, scaling before the clock is drawn so its font
+    // keeps its size
+    let encoders: Vec<Encoder> = options
+        .sizes
+        .iter()
+        .map(|&size| {
+            let mut encoder = encoder.clone();
+            encoder.filters = resize_filter(plan.render_size, size)
+                .into_iter()
+                .chain(filters.iter().cloned())
+                .collect();
+            encoder
+        })
+        .collect();
+    let input = |encoder: &Encoder| {
+        if encode_segments > 1 {
+            encoder.join(&frames_directory.join("_segments.txt"))
+        } else if use_concat {
+            encoder.encode_list(&concat_list)
+        } else {
+            encoder.encode_pattern(frames_directory, first_frame, options.terminal.extension())
+        }
+    };
+    // the first pass of --two-pass only writes the statistics of the second
+    // one to the pass log
+    let ffmpeg_commands: Vec<(Option<Command>, Command)> = encoders
+        .iter()
+        .zip(&output_paths)
+        .map(|(encoder, output_path)| {
+            let (first_pass, encoder) = if two_pass {
+                let log = passlog_prefix(output_path);
+                let mut first_pass = input(&Encoder {
+                    pass: Some((1, log.clone())),
+                    ..encoder.clone()
+                });
+                first_pass
+                    .args(&options.ffmpeg_args)
+                    .args(["-an", "-f", "null"])
+                    .arg(if cfg!(windows) { "NUL" } else { "/dev/null" });
+                let encoder = Encoder {
+                    pass: Some((2, log)),
+                    ..encoder.clone()
+                };
+                (Some(first_pass), encoder)
+            } else {
+                (None, encoder.clone())
+            };
+            let mut ffmpeg = input(&encoder);
+            match (loop_mode, format) {
+                (Some(loop_mode), VideoFormat::Gif) => {
+                    ffmpeg.arg("-loop").arg(loop_mode.gif_loop());
+                },
+                (Some(loop_mode), VideoFormat::Apng) => {
+                    ffmpeg.arg("-plays").arg(loop_mode.apng_plays());
+                },
+                _ => (),
+            }
+            ffmpeg.arg("-metadata").arg(format!("comment={}", comment));
+            ffmpeg.args(&options.ffmpeg_args);
+            ffmpeg.arg(output_path);
+            (first_pass, ffmpeg)
+        })
+        .collect();
+    Ok(Encoding {
+        output_paths,
+        encoders,
+        ffmpeg_commands,
+        use_concat,
+        concat_list,
+        poster,
+        poster_path,
+        encode_segments,
+        comment,
+    })
+}
+
+/// Prints the gnuplot scripts and ffmpeg commands of a render without running
+/// them
+fn print_dry_run(
+    options: &RenderOptions,
+    plan: &RenderPlan,
+    encoding: &Encoding,
+) -> Result<(), ViewerError> {
+    let settings = &plan.settings;
+    let stdout = io::stdout();
+    let mut writer = stdout.lock();
+    if let Some(delay) = gif_delay(options, plan) {
+        writeln!(writer, "# gnuplot script of the animation")?;
+        write_gif_script(&mut writer, settings, &plan.frame_list, delay)?;
+        return Ok(());
+    }
+    let script_number = if options.stage == Stage::Video {
+        0
+    } else if options.dry_run_all {
+        plan.frame_list.len()
+    } else {
+        1
+    };
+    for &i in &plan.frame_list[..script_number] {
+        writeln!(writer, "# gnuplot script of frame {}", i)?;
+        write_gnuplot_script(&mut writer, settings, i)?;
+    }
+    if !options.stage.makes_video() {
+        return Ok(());
+    }
+    if encoding.encode_segments > 1 {
+        for segment in plan_segments(
+            &settings.frames_directory,
+            &plan.frame_list,
+            encoding.encode_segments,
+            options.format,
+        ) {
+            writeln!(writer, "# ffmpeg command of segment {:?}", segment.output)?;
+            writeln!(writer, "{:?}", segment.command(&encoding.encoders[0]))?;
+        }
+    }
+    for (((first_pass, ffmpeg), encoder), output_path) in encoding
+        .ffmpeg_commands
+        .iter()
+        .zip(&encoding.encoders)
+        .zip(&encoding.output_paths)
+    {
+        if let Some(first_pass) = first_pass {
+            writeln!(writer, "# ffmpeg command of the first pass")?;
+            writeln!(writer, "{:?}", first_pass)?;
+        }
+        writeln!(writer, "# ffmpeg command")?;
+        writeln!(writer, "{:?}", ffmpeg)?;
+        if encoding.poster.is_some() && options.format == VideoFormat::Mp4 {
+            writeln!(writer, "# ffmpeg command attaching the poster")?;
+            writeln!(
+                writer,
+                "{:?}",
+                encoder.attach_poster(
+                    output_path,
+                    &encoding.poster_path,
+                    &poster_video(output_path, options.format)
+                )
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Number of gnuplot workers, capped so their estimated memory fits into
+/// `--max-memory`
+fn worker_number(options: &RenderOptions, plan: &RenderPlan) -> usize {
+    let worker_num = options.workers;
+    match options.max_memory {
+        Some(max_memory) => {
+            let footprint =
+                estimate_gnuplot_memory(&plan.settings, plan.render_size, plan.first_frame);
+            let fitting = ((max_memory * 1024.0 * 1024.0 / footprint) as usize).max(1);
+            if fitting < worker_num {
+                warn!(
+                    "using {} instead of {} workers, each gnuplot needs about {:.0} MB of the \
+                     {} MB limit",
+                    fitting,
+                    worker_num,
+                    footprint / 1024.0 / 1024.0,
+                    max_memory
+                );
+                fitting
+            } else {
+                info!(
+                    "using {} workers, each gnuplot needs about {:.0} MB of the {} MB limit",
+                    worker_num,
+                    footprint / 1024.0 / 1024.0,
+                    max_memory
+                );
+                worker_num
+            }
+        },
+        None => {
+            info!("using {} workers", worker_num);
+            worker_num
+        },
+    }
+}
+
+/// Renders the frames of the plan on the workers of `pool`, or checks they
+/// are rendered already for the video stage
+fn render_frames(
+    options: &RenderOptions,
+    plan: &RenderPlan,
+    pool: &ThreadPool,
+) -> Result<RenderedFrames, ViewerError> {
+    let settings = &plan.settings;
+    let directory = settings.directory.as_path();
+    let frame_list = &plan.frame_list;
+    let stdin_manifest = options.stdin_manifest;
+    interrupt::install();
+    let (tx, rx) = sync::mpsc::channel::<FrameResult>(); // create a channel for counting
+    let frames: Vec<usize> = if options.stage == Stage::Video {
+        let missing: Vec<usize> = frame_list
+            .iter()
+            .cloned()
+            .filter(|&i| !frame_path(settings, i).exists())
+            .collect();
+        if !missing.is_empty() {
+            return Err(ViewerError::Other(format!(
+                "frames {:?} are missing, render them with the frames subcommand first",
+                missing
+            )));
+        }
+        Vec::new()
+    } else if options.resume {
+        let frames: Vec<usize> = frame_list
+            .iter()
+            .cloned()
+            .filter(|&i| !is_rendered(settings, i))
+            .collect();
+        info!(
+            "resume: {} of {} frames left to render",
+            frames.len(),
+            frame_list.len()
+        );
+        frames
+    } else if stdin_manifest {
+        Vec::new()
+    } else {
+        frame_list.clone()
+    };
+    let mut job_number = frames.len();
+    let submit = |i: usize| {
+        let tx = tx.clone();
+        let settings = settings.clone();
+        pool.execute(move || {
+            let start = Instant::now();
+            let result = if interrupt::is_interrupted() {
+                Err(ViewerError::Interrupted)
+            } else {
+                render_frame(&settings, i)
+            };
+            tx.send((i, result, start.elapsed()))
+                .expect("failed to send item through channel tx");
+        });
+    };
+    if options.reuse_gnuplot {
+        let queue = sync::Arc::new(sync::Mutex::new(frames.iter().cloned().collect()));
+        for _ in 0..pool.max_count() {
+            let tx = tx.clone();
+            let settings = settings.clone();
+            let queue = queue.clone();
+            pool.execute(move || render_frames_reusing_gnuplot(&settings, &queue, &tx));
+        }
+    } else if !options.watch {
+        for &i in &frames {
+            submit(i);
+        }
+    }
+    let mut watcher = if options.watch {
+        info!("watching {:?} for new sample files", directory);
+        Some(Watcher::new(
+            directory,
+            &settings.species,
+            &frames,
+            options.watch_timeout,
+        ))
+    } else {
+        None
+    };
+    let mut manifest = settings.records.clone().map(|records| {
+        info!("reading frames from stdin");
+        ManifestReader::new(directory, settings.sample_start, records)
+    });
+
+    // the number of frames read from stdin is unknown
+    let mut progress = if !options.progress || !io::stderr().is_terminal() || stdin_manifest {
+        None
+    } else {
+        Some(ProgressBar::new(job_number))
+    };
+    let mut retries: HashMap<usize, usize> = HashMap::new();
+    let mut failed = Vec::new();
+    let mut finished = 0;
+    let mut skipped = 0;
+    let mut timings = Timings::new();
+    let mut rendered = Vec::new();
+    let collected = (|| -> Result<(), ViewerError> {
+        while finished < job_number || manifest.as_ref().is_some_and(|m| !m.is_stopped()) {
+            let (i, result, time) = match (&mut watcher, &mut manifest) {
+                (Some(watcher), _) if !watcher.is_stopped() => {
+                    if interrupt::is_interrupted() {
+                        watcher.stop();
+                    }
+                    for i in watcher.poll() {
+                        submit(i);
+                    }
+                    if watcher.is_stopped() {
+                        job_number -= watcher.remaining().len();
+                    }
+                    match rx.recv_timeout(Duration::from_secs(1)) {
+                        Ok(received) => received,
+                        Err(sync::mpsc::RecvTimeoutError::Timeout) => continue,
+                        Err(e) => panic!("failed to receive item through channel rx: {}", e),
+                    }
+                },
+                (_, Some(manifest)) if !manifest.is_stopped() => {
+                    if interrupt::is_interrupted() {
+                        manifest.stop();
+                    }
+                    for i in manifest.poll()? {
+                        job_number += 1;
+                        submit(i);
+                    }
+                    match rx.recv_timeout(Duration::from_millis(100)) {
+                        Ok(received) => received,
+                        Err(sync::mpsc::RecvTimeoutError::Timeout) => continue,
+                        Err(e) => panic!("failed to receive item through channel rx: {}", e),
+                    }
+                },
+                _ => rx
+                    .recv()
+                    .expect("failed to receive item through channel rx"),
+            };
+            let error = match result {
+                Ok(status) => {
+                    if progress.is_none() {
+                        let color = if status == Some(0) {
+                            Color::Green
+                        } else {
+                            Color::Red
+                        };
+                        println!(
+                            "child {} finished with status {}",
+                            i,
+                            color::paint(&format!("{:?}", status), color)
+                        );
+                    }
+                    if status == Some(0) {
+                        timings.record(i, time);
+                        rendered.push(i);
+                        None
+                    } else {
+                        let retried = retries.entry(i).or_insert(0);
+                        if *retried < options.max_retries {
+                            *retried += 1;
+                            warn!(
+                                "child {} finished with status {:?}, retrying ({}/{})",
+                                i, status, retried, options.max_retries
+                            );
+                            submit(i);
+                            continue;
+                        }
+                        Some(ViewerError::Frame(
+                            i,
+                            Box::new(ViewerError::Other(format!(
+                                "gnuplot exited with status {:?}",
+                                status
+                            ))),
+                        ))
+                    }
+                },
+                Err(ViewerError::Interrupted) => {
+                    skipped += 1;
+                    finished += 1;
+                    continue;
+                },
+                Err(e) => Some(ViewerError::Frame(i, Box::new(e))),
+            };
+            if let Some(e) = error {
+                if options.keep_going {
+                    error!("{}", e);
+                    failed.push(i);
+                } else {
+                    return Err(e);
+                }
+            }
+            if let Some(progress) = progress.as_mut() {
+                progress.inc();
+            }
+            finished += 1;
+        }
+        Ok(())
+    })();
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    collected?;
+    timings.print_summary();
+    if options.timings {
+        timings.write_csv(&directory.join("_timings.csv"))?;
+    }
+    if interrupt::is_interrupted() {
+        println!(
+            "{}: {} of {} frames rendered, rerun with --resume to render the rest",
+            color::paint("interrupted", Color::Yellow),
+            finished - skipped - failed.len(),
+            job_number
+        );
+        return Err(ViewerError::Interrupted);
+    }
+    // frames whose samples never appeared are left out of the video
+    let (frame_list, last_frame) = match watcher.as_ref().and_then(|w| w.remaining().front()) {
+        Some(&first_missing) => {
+            warn!(
+                "watching stopped before samples {}:{} appeared",
+                first_missing, plan.last_frame
+            );
+            let frame_list: Vec<usize> = frame_list
+                .iter()
+                .cloned()
+                .take_while(|&i| i < first_missing)
+                .collect();
+            match frame_list.last() {
+                Some(&last_frame) => (frame_list, last_frame),
+                None => {
+                    return Err(ViewerError::Other(
+                        "no samples appeared while watching, video not created".to_owned(),
+                    ))
+                },
+            }
+        },
+        None => match manifest {
+            Some(manifest) => {
+                let mut frame_list = manifest.arrived().to_vec();
+                frame_list.sort_unstable();
+                match frame_list.last() {
+                    Some(&last_frame) => (frame_list, last_frame),
+                    None => {
+                        return Err(ViewerError::Other(
+                            "no frame was listed on stdin, video not created".to_owned(),
+                        ))
+                    },
+                }
+            },
+            None => (frame_list.clone(), plan.last_frame),
+        },
+    };
+    if options.manifest {
+        let manifest: Vec<json::Value> = frame_list
+            .iter()
+            .cloned()
+            .filter(|i| !failed.contains(i))
+            .map(|i| {
+                let (elevation, azimuth, zoom) = view_angles(settings, i);
+                let path = frame_path(settings, i);
+                json::Value::object(vec![
+                    ("frame", i.into()),
+                    ("image", path.to_string_lossy().into_owned().into()),
+                    ("time", frame_time(settings, i).into()),
+                    ("elevation", elevation.into()),
+                    ("azimuth", azimuth.into()),
+                    ("zoom", zoom.into()),
+                ])
+            })
+            .collect();
+        fs::write(
+            directory.join("_frames.json"),
+            format!("{}\n", json::Value::Array(manifest)),
+        )?;
+    }
+    if !failed.is_empty() {
+        failed.sort_unstable();
+        println!(
+            "{} of {} frames {}: {:?}",
+            failed.len(),
+            job_number,
+            color::paint("failed", Color::Red),
+            failed
+        );
+        if failed.len() == job_number {
+            return Err(ViewerError::Other(
+                "no frame was rendered, video not created".to_owned(),
+            ));
+        }
+        warn!("the failed frames are left out of the video");
+    }
+    if options.resume {
+        let missing: Vec<usize> = frame_list
+            .iter()
+            .cloned()
+            .filter(|&i| !frame_path(settings, i).exists())
+            .collect();
+        if !missing.is_empty() {
+            return Err(ViewerError::Other(format!(
+                "frames {:?} are missing after resume, video not created",
+                missing
+            )));
+        }
+    }
+    Ok(RenderedFrames {
+        frame_list,
+        last_frame,
+        failed,
+        rendered,
+    })
+}
+
+/// Assembles the rendered frames into the videos, attaching the poster and
+/// writing the sidecars
+fn encode_video(
+    options: &RenderOptions,
+    plan: &RenderPlan,
+    encoding: Encoding,
+    frames: RenderedFrames,
+    pool: &ThreadPool,
+) -> Result<(), ViewerError> {
+    let settings = &plan.settings;
+    let frames_directory = settings.frames_directory.as_path();
+    let format = options.format;
+    let RenderedFrames {
+        frame_list,
+        last_frame,
+        failed,
+        rendered,
+    } = frames;
+    let Encoding {
+        output_paths,
+        encoders,
+        ffmpeg_commands,
+        use_concat,
+        concat_list,
+        poster,
+        poster_path,
+        encode_segments,
+        comment,
+    } = encoding;
+
+    if let Some(i) = poster {
+        write_poster(settings, i, &poster_path)?;
+        println!("poster frame {} written to {:?}", i, poster_path);
+    }
+
+    let frame_duration = 1.0 / plan.frame_rate;
+    let listed: Vec<usize> = frame_list
+        .iter()
+        .cloned()
+        .filter(|i| !failed.contains(i))
+        .collect();
+    let listed = match options.loop_mode {
+        Some(mode) => mode.sequence(&listed),
+        None => listed,
+    };
+    let segments = if encode_segments > 1 {
+        let segments = plan_segments(frames_directory, &listed, encode_segments, format);
+        let (segment_tx, segment_rx) = sync::mpsc::channel();
+        for segment in &segments {
+            write_concat_list(&segment.list, settings, &segment.frames, frame_duration)?;
+            let mut command = segment.command(&encoders[0]);
+            let segment_tx = segment_tx.clone();
+            pool.execute(move || {
+                let status = spawn(command.stdin(Stdio::null()), "ffmpeg", "--ffmpeg-path")
+                    .and_then(|mut child| Ok(child.wait()?));
+                segment_tx
+                    .send(status)
+                    .expect("failed to send item through channel tx");
+            });
+        }
+        for segment in &segments {
+            let status = segment_rx
+                .recv()
+                .expect("failed to receive item through channel rx")?;
+            if !status.success() {
+                return Err(ViewerError::Other(format!(
+                    "encoding a segment failed with ffmpeg status {:?}, see {:?}",
+                    status.code(),
+                    segment.output
+                )));
+            }
+        }
+        let mut list = BufWriter::new(File::create(frames_directory.join("_segments.txt"))?);
+        for segment in &segments {
+            writeln!(list, "file {}", concat_quote(&segment.output))?;
+        }
+        list.flush()?;
+        segments
+    } else {
+        if use_concat {
+            write_concat_list(&concat_list, settings, &listed, frame_duration)?;
+        }
+        Vec::new()
+    };
+
+    for (((first_pass, mut ffmpeg), encoder), output_path) in ffmpeg_commands
+        .into_iter()
+        .zip(&encoders)
+        .zip(&output_paths)
+    {
+        if output_paths.len() > 1 {
+            println!("encoding {:?}", output_path);
+        }
+        if let Some(mut first_pass) = first_pass {
+            let status = spawn(
+                first_pass
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::inherit())
+                    .stderr(Stdio::inherit()),
+                "ffmpeg",
+                "--ffmpeg-path",
+            )?
+            .wait()?;
+            println!(
+                "first pass child process exited with status {:?}",
+                status.code()
+            );
+            if !status.success() {
+                remove_passlogs(&passlog_prefix(output_path))?;
+                return Err(ViewerError::Other(format!(
+                    "first pass of encoding {:?} failed with ffmpeg status {:?}",
+                    output_path,
+                    status.code()
+                )));
+            }
+        }
+        let child = spawn(
+            ffmpeg
+                .stdin(Stdio::null())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit()),
+            "ffmpeg",
+            "--ffmpeg-path",
+        )?;
+        let output = child.wait_with_output()?;
+        println!(
+            "video creation child process exited with status {:?}",
+            output.status.code()
+        );
+        if options.two_pass {
+            remove_passlogs(&passlog_prefix(output_path))?;
+        }
+        if !output.status.success() {
+            let pass = if options.two_pass {
+                "second pass of "
+            } else {
+                ""
+            };
+            return Err(ViewerError::Other(format!(
+                "{}encoding {:?} failed with ffmpeg status {:?}",
+                pass,
+                output_path,
+                output.status.code()
+            )));
+        }
+        if poster.is_some() && format == VideoFormat::Mp4 {
+            let poster_video = poster_video(output_path, format);
+            let status = spawn(
+                encoder
+                    .attach_poster(output_path, &poster_path, &poster_video)
+                    .stdin(Stdio::null()),
+                "ffmpeg",
+                "--ffmpeg-path",
+            )?
+            .wait()?;
+            if status.success() {
+                fs::rename(&poster_video, output_path)?;
+            } else {
+                warn!(
+                    "attaching the poster failed with ffmpeg status {:?}",
+                    status.code()
+                );
+            }
+        }
+    }
+    if !segments.is_empty() {
+        for segment in &segments {
+            fs::remove_file(&segment.list)?;
+            fs::remove_file(&segment.output)?;
+        }
+        fs::remove_file(frames_directory.join("_segments.txt"))?;
+    }
+    if options.clean {
+        for &i in &rendered {
+            fs::remove_file(frame_path(settings, i))?;
+            if settings.emit_scripts {
+                fs::remove_file(script_path(settings, i))?;
+            }
+        }
+        if use_concat && encode_segments == 1 {
+            fs::remove_file(&concat_list)?;
+        }
+        println!("removed {} frames rendered by this run", rendered.len());
+    }
+    if options.sidecar {
+        for output_path in &output_paths {
+            let sidecar = json::Value::object(vec![
+                ("comment", comment.clone().into()),
+                ("video", output_path.to_string_lossy().into_owned().into()),
+                ("sample_start", settings.sample_start.into()),
+                ("sample_number", settings.sample_number.into()),
+                ("sample_time", settings.sample_time.into()),
+                ("first_frame", plan.first_frame.into()),
+                ("last_frame", last_frame.into()),
+                ("frame_rate", plan.frame_rate.to_string().into()),
+                ("initial_rotation", settings.initial_rotation.into()),
+                ("rotation_speed", settings.rotation_speed.into()),
+                ("elevation", settings.elevation.into()),
+                ("elevation_speed", settings.elevation_speed.into()),
+                ("min_bounds", settings.min_bounds.clone().into()),
+                ("max_bounds", settings.max_bounds.clone().into()),
+            ]);
+            fs::write(output_path.with_extension("json"), format!("{}\n", sidecar))?;
+        }
+    }
+    failure_result(failed)
+}
+
+/// Position of the `--stats-overlay` label in `corner`, and whether it is a
+/// bottom one
+fn stats_position(corner: Corner) -> (&'static str, bool) {
+    match corner {
+        Corner::TopLeft => ("at screen 0.01, screen 0.98 left", false),
+        Corner::TopRight => ("at screen 0.99, screen 0.98 right", false),
+        Corner::BottomLeft => ("at screen 0.01, screen 0.02 left", true),
+        Corner::BottomRight => ("at screen 0.99, screen 0.02 right", true),
+    }
+}
+
+/// `ViewerError::PartialFailure` if any frame in `failed`
+fn failure_result(failed: Vec<usize>) -> Result<(), ViewerError> {
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(ViewerError::PartialFailure(failed))
+    }
+}
+
+/// Sets the log level from `-v` and `-q`, falling back to `RUST_LOG` and then
+/// warnings, unless a logger is set up already
+fn init_logger(verbose: u64, quiet: bool) {
+    let write_style = if color::stderr_enabled() {
+        env_logger::WriteStyle::Always
+    } else {
+        env_logger::WriteStyle::Never
+    };
+    let level = match (verbose, quiet) {
+        (_, true) => LevelFilter::Error,
+        (0, false) => {
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+                .write_style(write_style)
+                .try_init()
+                .ok();
+            return;
+        },
+        (1, false) => LevelFilter::Info,
+        (2, false) => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .write_style(write_style)
+        .try_init()
+        .ok();
+}
+
+/// Builds a `drawtext` filter stamping the simulation time, `start_time` at the
+/// first video frame and growing by `time_step` per frame, in `corner`
+fn clock_filter(corner: Corner, start_time: f64, time_step: f64, unit: &str) -> String {
+    let (x, y) = match corner {
+        Corner::TopLeft => ("10", "10"),
+        Corner::TopRight => ("w-tw-10", "10"),
+        Corner::BottomLeft => ("10", "h-th-10"),
+        Corner::BottomRight => ("w-tw-10", "h-th-10"),
+    };
+    let unit = unit
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace(':', "\\:");
+    format!(
+        "drawtext=text='time = %{{expr\\:{}+n*{}}} {}':x={}:y={}:fontsize=24:fontcolor=white:\
+         box=1:boxcolor=black@0.5:boxborderw=5",
+        start_time, time_step, unit, x, y
+    )
+}
+
+/// The `scale` filter making a video of `size` from frames of `rendered` size,
+/// cropping the middle of frames of another aspect ratio
+fn resize_filter(rendered: (f64, f64), size: (f64, f64)) -> Option<String> {
+    if rendered == size {
+        None
+    } else if rendered.0 * size.1 == rendered.1 * size.0 {
+        Some(format!("scale={}:{}:flags=lanczos", size.0, size.1))
+    } else {
+        Some(format!(
+            "scale={0}:{1}:force_original_aspect_ratio=increase:flags=lanczos,crop={0}:{1}",
+            size.0, size.1
+        ))
+    }
+}
+
+/// The `-passlogfile` prefix of the two-pass encode of `output_path`
+fn passlog_prefix(output_path: &Path) -> PathBuf {
+    output_path.with_extension("passlog")
+}
+
+/// Removes the files ffmpeg wrote for the pass log `prefix`, like
+/// `{prefix}-0.log` and `{prefix}-0.log.mbtree` of libx264
+fn remove_passlogs(prefix: &Path) -> io::Result<()> {
+    let directory = match prefix.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = prefix.file_name().unwrap_or_default().to_string_lossy();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(&*name) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// The temporary video of `output_path` with the poster attached
+fn poster_video(output_path: &Path, format: VideoFormat) -> PathBuf {
+    output_path.with_extension(format!("poster.{}", format.extension()))
+}
+
+/// ffmpeg options shared by the video and its segments
+#[derive(Clone)]
+struct Encoder {
+    program: String,
+    frame_rate: String,
+    format: VideoFormat,
+    crf: Option<u32>,
+    bitrate: Option<String>,
+    /// `-pix_fmt` keeping transparency
+    pixel_format: Option<&'static str>,
+    /// Video filters chained into `-vf`
+    filters: Vec<String>,
+    /// Number of the pass and the `-passlogfile` prefix of a two-pass encode
+    pass: Option<(u8, PathBuf)>,
+}
+
+impl Encoder {
+    /// Encodes `%d.png`, or the frames of another `extension`, of `directory`
+    /// from `first_frame` on until a number is missing
+    fn encode_pattern(&self, directory: &Path, first_frame: usize, extension: &str) -> Command {
+        let mut ffmpeg = Command::new(&self.program);
+        ffmpeg
+            .arg("-y")
+            .arg("-r")
+            .arg(&self.frame_rate)
+            .arg("-start_number")
+            .arg(first_frame.to_string())
+            .arg("-i")
+            .arg(directory.join(format!("%d.{}", extension)));
+        self.codec_args(&mut ffmpeg);
+        ffmpeg
+    }
+
+    /// Encodes the frames of a list written by `write_concat_list`
+    fn encode_list(&self, list: &Path) -> Command {
+        let mut ffmpeg = Command::new(&self.program);
+        ffmpeg
+            .args(["-y", "-f", "concat", "-safe", "0", "-i"])
+            .arg(list)
+            .arg("-r")
+            .arg(&self.frame_rate);
+        self.codec_args(&mut ffmpeg);
+        ffmpeg
+    }
+
+    /// Joins encoded segments listed in `list` without encoding them again
+    fn join(&self, list: &Path) -> Command {
+        let mut ffmpeg = Command::new(&self.program);
+        ffmpeg
+            .args(["-y", "-f", "concat", "-safe", "0", "-i"])
+            .arg(list)
+            .args(["-c", "copy"]);
+        ffmpeg
+    }
+
+    /// Copies `video` into `output` with the image `poster` attached as cover
+    fn attach_poster(&self, video: &Path, poster: &Path, output: &Path) -> Command {
+        let mut ffmpeg = Command::new(&self.program);
+        ffmpeg
+            .arg("-y")
+            .arg("-i")
+            .arg(video)
+            .arg("-i")
+            .arg(poster)
+            .args(["-map", "0", "-map", "1", "-c", "copy", "-c:v:1", "png"])
+            .args(["-disposition:v:1", "attached_pic"])
+            .arg(output);
+        ffmpeg
+    }
+
+    fn codec_args(&self, ffmpeg: &mut Command) {
+        ffmpeg.args(["-c:v", self.format.codec()]);
+        // the png extension would pick the image muxer
+        if self.format == VideoFormat::Apng {
+            ffmpeg.args(["-f", "apng"]);
+        }
+        if let Some(crf) = self.crf {
+            ffmpeg.arg("-crf").arg(crf.to_string());
+        }
+        if let Some(ref bitrate) = self.bitrate {
+            ffmpeg.arg("-b:v").arg(bitrate);
+        }
+        if let Some((pass, ref log)) = self.pass {
+            ffmpeg
+                .arg("-pass")
+                .arg(pass.to_string())
+                .arg("-passlogfile")
+                .arg(log);
+        }
+        if let Some(pixel_format) = self.pixel_format {
+            ffmpeg.arg("-pix_fmt").arg(pixel_format);
+        }
+        if !self.filters.is_empty() {
+            ffmpeg.arg("-vf").arg(self.filters.join(","));
+        }
+    }
+}
+
+/// Contiguous part of the frames encoded on its own by `--encode-segments`
+struct Segment {
+    frames: Vec<usize>,
+    /// Concat list of the frames
+    list: PathBuf,
+    output: PathBuf,
+}
+
+impl Segment {
+    fn command(&self, encoder: &Encoder) -> Command {
+        let mut ffmpeg = encoder.encode_list(&self.list);
+        ffmpeg.arg(&self.output);
+        ffmpeg
+    }
+}
+
+/// Splits `frames` into at most `number` segments of equal length
+///
+/// Each segment is a video of its own starting with a key frame, so their
+/// streams can be joined without encoding them again.
+fn plan_segments(
+    directory: &Path,
+    frames: &[usize],
+    number: usize,
+    format: VideoFormat,
+) -> Vec<Segment> {
+    frames
+        .chunks(frames.len().div_ceil(number).max(1))
+        .enumerate()
+        .map(|(k, frames)| Segment {
+            frames: frames.to_vec(),
+            list: directory.join(format!("_segment{}.txt", k)),
+            output: directory.join(format!("_segment{}.{}", k, format.extension())),
+        })
+        .collect()
+}
+
+/// Quotes `path` for a list of the concat demuxer, where a `'` is written as
+/// `'\''` like in the shell
+fn concat_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "'\\''"))
+}
+
+/// Writes a list of the images of `frames` for ffmpeg's concat demuxer, each
+/// shown for `frame_duration` seconds
+fn write_concat_list(
+    path: &Path,
+    settings: &PlotSettings,
+    frames: &[usize],
+    frame_duration: f64,
+) -> io::Result<()> {
+    let mut list = BufWriter::new(File::create(path)?);
+    for &i in frames {
+        writeln!(list, "file {}", concat_quote(&frame_path(settings, i)))?;
+        writeln!(list, "duration {}", frame_duration)?;
+    }
+    // the concat demuxer ignores the duration of the last file
+    if let Some(&i) = frames.last() {
+        writeln!(list, "file {}", concat_quote(&frame_path(settings, i)))?;
+    }
+    list.flush()
+}
+
+/// Writes the terminal and view setup shared by every frame
+fn write_gnuplot_header<W: Write>(writer: &mut W, settings: &PlotSettings) -> io::Result<()> {
+    let terminal = match settings.terminal {
+        Terminal::Png => "pngcairo",
+        Terminal::Jpeg => "jpeg",
+        Terminal::Svg => "svg",
+        Terminal::Pdf => "pdfcairo",
+    };
+    write!(
+        writer,
+        "set terminal {} size {} enhanced font 'Verdana,10'",
+        terminal, settings.size
+    )?;
+    // keep text, lines and points in proportion to the larger canvas
+    if settings.supersample > 1 {
+        write!(
+            writer,
+            " fontscale {0} linewidth {0} pointscale {0}",
+            settings.supersample
+        )?;
+    }
+    // svg has no background unless one is set
+    if settings.transparent && settings.terminal != Terminal::Svg {
+        write!(writer, " transparent background rgb '#00000000'")?;
+    } else if let Some(ref background) = settings.background {
+        write!(writer, " background rgb '{}'", background)?;
+    }
+    writeln!(writer)?;
+    write_gnuplot_setup(writer, settings)
+}
+
+/// Writes the axes, data and palette setup following the terminal line
+fn write_gnuplot_setup<W: Write>(writer: &mut W, settings: &PlotSettings) -> io::Result<()> {
+    match settings.aspect {
+        Some(ref aspect) if aspect.len() == 2 => {
+            writeln!(writer, "set size ratio {}", aspect[1] / aspect[0])?
+        },
+        Some(_) => (),
+        None if settings.equal_axes => writeln!(writer, "set view equal xyz")?,
+        None => (),
+    }
+    writeln!(writer, "set xyplane relative 0")?;
+    if settings.grid {
+        writeln!(writer, "set grid")?;
+    }
+    if !settings.border {
+        writeln!(writer, "unset border")?;
+    }
+    if let Some(ref axes) = settings.log_scale {
+        writeln!(writer, "set logscale {}", axes)?;
+    }
+    for (axis, label) in ["x", "y", "z"].iter().zip(&settings.axis_labels) {
+        writeln!(writer, "set {}label '{}'", axis, label.replace('\'', "''"))?;
+    }
+    if let Some(separator) = settings.separator {
+        writeln!(writer, "set datafile separator '{}'", separator)?;
+    }
+    if settings.heatmap.is_some() {
+        writeln!(writer, "{}", settings.palette)?;
+        if !settings.colorbar {
+            writeln!(writer, "unset colorbox")?;
+        }
+    } else if let Some((min, max)) = settings.color_range {
+        writeln!(writer, "{}", settings.palette)?;
+        writeln!(writer, "set cbrange [{}:{}]", min, max)?;
+        if settings.colorbar {
+            // leave room on the right for the color box and its tics
+            writeln!(writer, "set colorbox")?;
+            writeln!(writer, "set rmargin 12")?;
+        } else {
+            writeln!(writer, "unset colorbox")?;
+        }
+    }
+    // last, so it can override everything set above
+    if let Some(ref prelude) = settings.prelude {
+        writeln!(writer, "{}", prelude.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Writes a script rendering the color scale alone into `_colorbar.png` of
+/// `height` pixels
+fn write_colorbar_script<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    height: f64,
+) -> io::Result<()> {
+    let (min, max) = settings.color_range.unwrap_or((0.0, 1.0));
+    write!(
+        writer,
+        "set terminal pngcairo size 160,{} enhanced font 'Verdana,10'",
+        height
+    )?;
+    if settings.transparent {
+        write!(writer, " transparent background rgb '#00000000'")?;
+    } else if let Some(ref background) = settings.background {
+        write!(writer, " background rgb '{}'", background)?;
+    }
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "set output {:?}",
+        settings.directory.join("_colorbar.png")
+    )?;
+    writeln!(writer, "{}", settings.palette)?;
+    writeln!(writer, "set cbrange [{}:{}]", min, max)?;
+    writeln!(writer, "unset border")?;
+    writeln!(writer, "unset xtics")?;
+    writeln!(writer, "unset ytics")?;
+    writeln!(writer, "unset key")?;
+    writeln!(
+        writer,
+        "set colorbox vertical user origin screen 0.2,0.05 size screen 0.25,0.9"
+    )?;
+    // nothing is drawn but the color box of the palette plot
+    writeln!(writer, "plot [0:1] [0:1] NaN linecolor palette")
+}
+
+/// Writes the output and plot of frame `i`
+fn write_gnuplot_frame<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+) -> io::Result<()> {
+    writeln!(writer, "set output {:?}", frame_path(settings, i))?;
+    write_gnuplot_panels(writer, settings, i)
+}
+
+/// Writes the plot of frame `i`, with the `--mosaic` panels in a grid if any
+fn write_gnuplot_panels<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+) -> io::Result<()> {
+    if settings.panels.is_empty() {
+        return write_gnuplot_plot(writer, settings, i);
+    }
+    let number = settings.panels.len() + 1;
+    let columns = (number as f64).sqrt().ceil() as usize;
+    writeln!(
+        writer,
+        "set multiplot layout {},{}",
+        number.div_ceil(columns),
+        columns
+    )?;
+    for panel in iter::once(settings).chain(&settings.panels) {
+        let name = panel.directory.file_name().unwrap_or_default();
+        writeln!(
+            writer,
+            "set title '{}'",
+            name.to_string_lossy().replace('\'', "''")
+        )?;
+        write_gnuplot_plot(writer, panel, i)?;
+    }
+    writeln!(writer, "unset multiplot")
+}
+
+/// Elevation, azimuth and zoom of the camera in frame `i`, moving relative to
+/// the first sample unless a camera path is given
+fn view_angles(settings: &PlotSettings, i: usize) -> (f64, f64, f64) {
+    if let Some(ref camera) = settings.camera {
+        let key = camera.at(i);
+        return (key.elevation % 360f64, key.azimuth % 360f64, key.zoom);
+    }
+    let offset = (i - settings.sample_start) as f64;
+    let progress = if settings.sample_number == 0 {
+        0.0
+    } else {
+        offset / settings.sample_number as f64
+    };
+    let (elevation, azimuth) = match settings.rotate_axis {
+        RotateAxis::Z => (
+            settings.elevation + offset * settings.elevation_speed,
+            settings.initial_rotation + offset * settings.rotation_speed,
+        ),
+        // the elevation turns around the horizontal screen axis, which is the
+        // x axis seen from azimuth 0 and the y axis seen from azimuth 90
+        RotateAxis::X => (settings.elevation + offset * settings.rotation_speed, 0.0),
+        RotateAxis::Y => (settings.elevation + offset * settings.rotation_speed, 90.0),
+    };
+    (
+        elevation % 360f64,
+        azimuth % 360f64,
+        settings.zoom_start + (settings.zoom_end - settings.zoom_start) * progress,
+    )
+}
+
+/// Tag of the `--stats-overlay` label, high enough to leave the labels of a
+/// `--gnuplot-prelude` alone
+const STATS_LABEL_TAG: u32 = 1000;
+
+/// Widens the narrower of the x and y ranges around its middle, so the width
+/// of the bounds is `ratio` times their height
+fn fit_aspect((mut min_bounds, mut max_bounds): Bounds, ratio: f64) -> Bounds {
+    let width = max_bounds[0] - min_bounds[0];
+    let height = max_bounds[1] - min_bounds[1];
+    let (axis, growth) = if width < height * ratio {
+        (0, height * ratio - width)
+    } else {
+        (1, width / ratio - height)
+    };
+    min_bounds[axis] -= growth / 2.0;
+    max_bounds[axis] += growth / 2.0;
+    (min_bounds, max_bounds)
+}
+
+/// Writes the `--stats-overlay` label of frame `i`, the number of points and
+/// their range along each axis
+fn write_stats_label<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+    (position, bottom): (&str, bool),
+) -> io::Result<()> {
+    let points = read_frame_points(settings, i)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let mut lines = vec![format!("points: {}", points.len())];
+    let dimension = points.first().map_or(0, Vec::len);
+    for (d, axis) in ["x", "y", "z"].iter().enumerate().take(dimension) {
+        let (min, max) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), point| {
+                (min.min(point[d]), max.max(point[d]))
+            });
+        lines.push(format!("{}: [{:.4}:{:.4}]", axis, min, max));
+    }
+    // a label is anchored at its first line, so bottom labels are moved up
+    let offset = if bottom { lines.len() - 1 } else { 0 };
+    writeln!(
+        writer,
+        "set label {} \"{}\" {} offset 0,{} front noenhanced",
+        STATS_LABEL_TAG,
+        lines.join("\\n"),
+        position,
+        offset
+    )
+}
+
+/// Simulation time of frame `i` in seconds
+fn frame_time(settings: &PlotSettings, i: usize) -> f64 {
+    settings
+        .records
+        .as_ref()
+        .and_then(|records| {
+            records
+                .read()
+                .expect("manifest lock poisoned")
+                .get(&i)?
+                .time
+        })
+        .unwrap_or(settings.sample_time * i as f64)
+}
+
+/// Range of each axis drawn in frame `i`
+///
+/// With `--follow-com` the bounds keep their size and are centered on the
+/// centroid of the frame, with `--smooth-bounds` they are the frame's own.
+fn view_window(settings: &PlotSettings, i: usize) -> Vec<(f64, f64)> {
+    if let Some((min_bounds, max_bounds)) = settings.frame_bounds.get(&i) {
+        return min_bounds
+            .iter()
+            .cloned()
+            .zip(max_bounds.iter().cloned())
+            .collect();
+    }
+    (0..settings.min_bounds.len())
+        .map(|d| {
+            let shift = settings.centers.get(&i).map_or(0.0, |center| {
+                center[d] - (settings.min_bounds[d] + settings.max_bounds[d]) / 2.0
+            });
+            (
+                settings.min_bounds[d] + shift,
+                settings.max_bounds[d] + shift,
+            )
+        })
+        .collect()
+}
+
+/// Writes the view angle and plot command of frame `i`
+fn write_gnuplot_plot<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+) -> io::Result<()> {
+    let time_point = frame_time(settings, i) * settings.time_scale;
+    let title = settings.title_format.as_ref().map(|format| {
+        format
+            .render(
+                time_point,
+                &settings.time_unit,
+                i,
+                settings.sample_start + settings.sample_number,
+            )
+            .replace('\'', "''")
+    });
+    let dimension = settings.min_bounds.len();
+    if let Some(position) = settings.stats_overlay {
+        write_stats_label(writer, settings, i, position)?;
+    }
+
+    let (elevation, azimuth, zoom) = view_angles(settings, i);
+    write!(writer, "set view {},{}", elevation, azimuth)?;
+    match settings.aspect {
+        // x and y share the same length in 3D, only z is scaled relative to them
+        Some(ref aspect) if aspect.len() == 3 => {
+            writeln!(writer, ",{},{}", zoom, aspect[2] / aspect[0])?
+        },
+        _ if zoom != 1.0 => writeln!(writer, ",{}", zoom)?,
+        _ => writeln!(writer)?,
+    }
+    if dimension == 3 {
+        write!(writer, "splot ")?;
+    } else {
+        write!(writer, "plot ")?;
+    }
+    // write bounds, 1D data is plotted against the point index on the x axis
+    if dimension == 1 {
+        write!(writer, "[*:*] ")?;
+    }
+    for (min, max) in view_window(settings, i) {
+        write!(writer, "[{}:{}] ", min, max)?;
+    }
+    if let Some(bins) = settings.heatmap {
+        return write_heatmap(writer, settings, i, bins, title.as_deref());
+    }
+    // files in the order of the plot clauses, whose data follows the command
+    // with --inline-data, the number of points kept of them and the color
+    // jittered for their points
+    let mut sources: Vec<(PathBuf, Option<usize>, Option<u32>)> = Vec::new();
+    // the overlay is the same in every frame and drawn below everything else
+    if let Some(ref overlay) = settings.overlay {
+        let using = match dimension {
+            1 => "0:1",
+            2 => "1:2",
+            _ if settings.pseudo_3d => "1:2:(0)",
+            _ => "1:2:3",
+        };
+        write!(
+            writer,
+            "{} using {} notitle {}, ",
+            plot_source(settings, overlay),
+            using,
+            settings.overlay_style
+        )?;
+        sources.push((overlay.clone(), None, None));
+    }
+    // trails from the oldest frame on, drawn below the current one
+    for j in i
+        .saturating_sub(settings.trail_length)
+        .max(settings.sample_start)..i
+    {
+        let point_size = settings.point_size.unwrap_or(1.0)
+            * (1.0 - (i - j) as f64 / (settings.trail_length + 1) as f64);
+        for (trail_path, species) in sample_sources(settings, j) {
+            write_plot_source(
+                writer,
+                settings,
+                &trail_path,
+                j,
+                species,
+                None,
+                Some(point_size),
+            )?;
+            write!(writer, ", ")?;
+            let base = jitter_base(settings, species);
+            sources.push((trail_path, settings.max_points, base));
+        }
+    }
+    // the frame title is attached to the first source only
+    for (n, (input_path, species)) in sample_sources(settings, i).into_iter().enumerate() {
+        if n > 0 {
+            write!(writer, ", ")?;
+        }
+        write_plot_source(
+            writer,
+            settings,
+            &input_path,
+            i,
+            species,
+            title.as_deref().filter(|_| n == 0),
+            settings.point_size,
+        )?;
+        let base = jitter_base(settings, species);
+        sources.push((input_path, settings.max_points, base));
+    }
+    writeln!(writer)?;
+    if settings.inline_data {
+        for (path, max_points, base) in sources {
+            let lines = read_data_lines(&path)?;
+            let lines = match max_points {
+                Some(max_points) => subsample(lines, max_points),
+                None => lines,
+            };
+            for (n, line) in lines.into_iter().enumerate() {
+                match (&settings.color_jitter, base) {
+                    (Some(jitter), Some(base)) => {
+                        let key = match jitter.id_column {
+                            Some(c) => match split_fields(&line, settings.separator).get(c - 1) {
+                                Some(id) => jitter::id_key(id),
+                                None => {
+                                    return Err(io::Error::new(
+                                        io::ErrorKind::InvalidData,
+                                        format!(
+                                            "{:?}: id column {} missing in line {:?}",
+                                            path, c, line
+                                        ),
+                                    ))
+                                },
+                            },
+                            None => n as u64,
+                        };
+                        // the color is column 1, shifting the data columns
+                        writeln!(
+                            writer,
+                            "{}{}{}",
+                            jitter.color(base, key),
+                            settings.separator.unwrap_or(' '),
+                            line
+                        )?;
+                    },
+                    _ => writeln!(writer, "{}", line)?,
+                }
+            }
+            writeln!(writer, "e")?;
+        }
+    }
+    Ok(())
+}
+
+/// The color jittered for the points of a source of `species`, `None` without
+/// `--color-jitter`
+fn jitter_base(settings: &PlotSettings, species: Option<&Species>) -> Option<u32> {
+    settings.color_jitter.as_ref()?;
+    let color = species
+        .map(|s| s.color.as_str())
+        .or(settings.point_color.as_deref());
+    Some(
+        color
+            .and_then(jitter::parse_rgb)
+            .unwrap_or(jitter::DEFAULT_COLOR),
+    )
+}
+
+/// Keeps `number` of `lines` in their order, picked pseudo-randomly
+///
+/// The generator starts from the same seed every time, so files with the same
+/// number of lines keep the same lines and points do not flicker between
+/// frames.
+fn subsample(lines: Vec<String>, number: usize) -> Vec<String> {
+    if lines.len() <= number {
+        return lines;
+    }
+    // xorshift64, good enough to spread the picks evenly
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    // partial Fisher-Yates shuffle of the first `number` indices
+    let mut indices: Vec<usize> = (0..lines.len()).collect();
+    for k in 0..number {
+        let j = k + (next() % (lines.len() - k) as u64) as usize;
+        indices.swap(k, j);
+    }
+    let mut kept = indices[..number].to_vec();
+    kept.sort_unstable();
+    let mut lines: Vec<Option<String>> = lines.into_iter().map(Some).collect();
+    kept.into_iter()
+        .filter_map(|index| lines[index].take())
+        .collect()
+}
+
+/// Writes the clause of a density image of frame `i` following the bounds of
+/// a `plot` command, with the point counts of `bins` by `bins` cells inline
+fn write_heatmap<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+    bins: usize,
+    title: Option<&str>,
+) -> io::Result<()> {
+    let points = read_frame_points(settings, i)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let (min_bounds, max_bounds): (Vec<f64>, Vec<f64>) =
+        view_window(settings, i).into_iter().unzip();
+    let counts = histogram(&points, &min_bounds, &max_bounds, bins);
+    write!(writer, "'-' using 1:2:3 ")?;
+    match title {
+        Some(title) => write!(writer, "title '{}' ", title)?,
+        None => write!(writer, "notitle ")?,
+    }
+    writeln!(writer, "with image")?;
+    // cells are written at their centers, one scan line per row
+    let step: Vec<f64> = (0..2)
+        .map(|d| (max_bounds[d] - min_bounds[d]) / bins as f64)
+        .collect();
+    for (row, cells) in counts.iter().enumerate() {
+        let y = min_bounds[1] + (row as f64 + 0.5) * step[1];
+        for (column, count) in cells.iter().enumerate() {
+            let x = min_bounds[0] + (column as f64 + 0.5) * step[0];
+            writeln!(writer, "{} {} {}", x, y, count)?;
+        }
+        writeln!(writer)?;
+    }
+    writeln!(writer, "e")
+}
+
+/// Counts the 2D points in each of `bins` by `bins` cells of the bounds,
+/// indexed by row along y, then column along x
+///
+/// Points outside the bounds are not counted.
+fn histogram(
+    points: &[Vec<f64>],
+    min_bounds: &[f64],
+    max_bounds: &[f64],
+    bins: usize,
+) -> Vec<Vec<usize>> {
+    let mut counts = vec![vec![0; bins]; bins];
+    let cell = |value: f64, d: usize| {
+        let offset = (value - min_bounds[d]) / (max_bounds[d] - min_bounds[d]);
+        if (0.0..=1.0).contains(&offset) {
+            Some(((offset * bins as f64) as usize).min(bins - 1))
+        } else {
+            None
+        }
+    };
+    for point in points {
+        if let (Some(column), Some(row)) = (cell(point[0], 0), cell(point[1], 1)) {
+            counts[row][column] += 1;
+        }
+    }
+    counts
+}
+
+/// Writes one data file clause of a `plot`/`splot` command
+///
+/// A missing title writes `notitle`. A species overrides the point type and
+/// color. With trails enabled every other source is drawn in the first line
+/// color, otherwise gnuplot would color them one by one.
+fn write_plot_source<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    path: &Path,
+    frame: usize,
+    species: Option<&Species>,
+    title: Option<&str>,
+    point_size: Option<f64>,
+) -> io::Result<()> {
+    let dimension = settings.min_bounds.len();
+    write!(writer, "{} ", plot_source(settings, path))?;
+    if settings.indexed_file.is_some() {
+        write!(writer, "index {} ", frame - settings.sample_start)?;
+    }
+    if let Some(every) = settings.every {
+        write!(writer, "every {} ", every)?;
+    }
+    if dimension == 1
+        || settings.pseudo_3d
+        || settings.columns.is_some()
+        || settings.color_column.is_some()
+        || settings.color_jitter.is_some()
+        || settings.vectors.is_some()
+    {
+        // the z column of pseudo 3D plots is not in the data
+        let data_dimension = dimension - settings.pseudo_3d as usize;
+        let coordinates = match settings.columns {
+            Some(ref columns) => columns.clone(),
+            None => (1..=data_dimension).collect(),
+        };
+        // jittered colors are written before the data
+        let shift = settings.color_jitter.is_some() as usize;
+        let coordinates: Vec<usize> = coordinates.into_iter().map(|c| c + shift).collect();
+        let mut using = coordinates
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(":");
+        if dimension == 1 {
+            using.insert_str(0, "0:");
+        }
+        if settings.pseudo_3d {
+            using.push_str(":(0)");
+        }
+        if let Some(ref vectors) = settings.vectors {
+            for c in vectors {
+                using.push_str(&format!(":(${}*{})", c + shift, settings.vector_scale));
+            }
+            if settings.pseudo_3d {
+                using.push_str(":(0)");
+            }
+        }
+        if let Some(column) = settings.color_column {
+            using.push_str(&format!(":{}", column));
+        }
+        if settings.color_jitter.is_some() {
+            using.push_str(":1");
+        }
+        write!(writer, "using {} ", using)?;
+    }
+    match title {
+        Some(title) => write!(writer, "title '{}' ", title)?,
+        None => write!(writer, "notitle ")?,
+    }
+    if settings.vectors.is_some() {
+        write!(writer, "with vectors")?;
+    } else {
+        if settings.color_column.is_some() {
+            write!(writer, "with points ")?;
+        }
+        let point_type = species.map_or(
+            &settings.point_types[frame % settings.point_types.len()],
+            |s| &s.point_type,
+        );
+        write!(writer, "pointtype {}", point_type)?;
+        if let Some(point_size) = point_size {
+            write!(writer, " pointsize {}", point_size)?;
+        }
+    }
+    if settings.color_column.is_some() {
+        write!(writer, " palette")?;
+    } else if settings.color_jitter.is_some() {
+        write!(writer, " linecolor rgb variable")?;
+    } else if let Some(species) = species {
+        write!(writer, " linecolor rgb '{}'", species.color)?;
+    } else if let Some(ref point_color) = settings.point_color {
+        write!(writer, " linecolor rgb '{}'", point_color)?;
+    } else if settings.trail_length > 0 {
+        write!(writer, " linecolor 1")?;
+    }
+    Ok(())
+}
+
+/// Writes the complete gnuplot script rendering frame `i`
+fn write_gnuplot_script<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    i: usize,
+) -> io::Result<()> {
+    write_gnuplot_header(writer, settings)?;
+    write_gnuplot_frame(writer, settings, i)
+}
+
+/// Writes a script rendering all `frames` into one animated GIF with `delay`
+/// hundredths of a second between frames
+fn write_gif_script<W: Write>(
+    writer: &mut W,
+    settings: &PlotSettings,
+    frames: &[usize],
+    delay: u32,
+) -> io::Result<()> {
+    write!(
+        writer,
+        "set terminal gif animate delay {} size {} enhanced font 'Verdana,10'",
+        delay, settings.size
+    )?;
+    if settings.transparent {
+        write!(writer, " transparent")?;
+    } else if let Some(ref background) = settings.background {
+        write!(writer, " background rgb '{}'", background)?;
+    }
+    writeln!(writer)?;
+    write_gnuplot_setup(writer, settings)?;
+    writeln!(
+        writer,
+        "set output {:?}",
+        settings.directory.join("_animation.gif")
+    )?;
+    for &i in frames {
+        write_gnuplot_plot(writer, settings, i)?;
+    }
+    Ok(())
+}
+
+/// Writes the gnuplot script of frame `i` to `{i}.gp` if requested
+fn emit_gnuplot_script(settings: &PlotSettings, i: usize) -> io::Result<()> {
+    if settings.emit_scripts {
+        let file = File::create(script_path(settings, i))?;
+        let mut writer = BufWriter::new(file);
+        write_gnuplot_script(&mut writer, settings, i)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Fails early if the sample file of frame `i` does not exist, since gnuplot
+/// would otherwise quietly render an empty frame
+fn check_sample_file(settings: &PlotSettings, i: usize) -> Result<(), ViewerError> {
+    match iter::once(settings)
+        .chain(&settings.panels)
+        .flat_map(|panel| sample_sources(panel, i).into_iter().map(|(path, _)| path))
+        .find(|path| !path.exists())
+    {
+        None => Ok(()),
+        Some(path) => Err(ViewerError::Other(format!(
+            "missing sample file {}(.gz)",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ))),
+    }
+}
+
+fn spawn_gnuplot(settings: &PlotSettings) -> Result<Child, ViewerError> {
+    let mut command = Command::new(&settings.gnuplot_path);
+    command
+        .args(&settings.gnuplot_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+    // keep Ctrl-C of the terminal from killing frames in flight
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    spawn(&mut command, "gnuplot", "--gnuplot-path")
+}
+
+/// Checks that `path` runs successfully with `version_arg` before rendering
+fn preflight(path: &str, version_arg: &str, program: &str, flag: &str) -> Result<(), ViewerError> {
+    let status = spawn(
+        Command::new(path)
+            .arg(version_arg)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null()),
+        program,
+        flag,
+    )?
+    .wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ViewerError::Other(format!(
+            "{:?} {} exited with status {:?}, check {}",
+            path,
+            version_arg,
+            status.code(),
+            flag
+        )))
+    }
+}
+
+/// Whether opened files can be shown, judged by the display variables on
+/// unix systems other than macOS
+fn has_display() -> bool {
+    !cfg!(unix)
+        || cfg!(target_os = "macos")
+        || env::var_os("DISPLAY").is_some()
+        || env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Opens `paths` with the default viewer of the platform, warning about
+/// files that could not be opened
+fn open_files(paths: &[PathBuf]) {
+    // `start` is built into cmd, its empty argument is the window title
+    let (opener, opener_args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("cmd", &["/C", "start", ""])
+    } else {
+        ("xdg-open", &[])
+    };
+    for path in paths {
+        println!("opening {:?}", path);
+        let status = Command::new(opener)
+            .args(opener_args)
+            .arg(path)
+            .stdin(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => (),
+            Ok(status) => warn!(
+                "{} {:?} exited with status {:?}",
+                opener,
+                path,
+                status.code()
+            ),
+            Err(e) => warn!("failed to run {}: {}", opener, e),
+        }
+    }
+}
+
+/// Spawns `command`, explaining how to fix a missing executable `program`
+/// configurable by `flag`
+fn spawn(command: &mut Command, program: &str, flag: &str) -> Result<Child, ViewerError> {
+    command.spawn().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            ViewerError::Other(format!(
+                "{:?} not found, install {} or set its location with {}",
+                command.get_program(),
+                program,
+                flag
+            ))
+        } else {
+            e.into()
+        }
+    })
+}
+
+/// Renders frame `i` with its own gnuplot process
+fn render_frame(settings: &PlotSettings, i: usize) -> Result<Option<i32>, ViewerError> {
+    check_sample_file(settings, i)?;
+    emit_gnuplot_script(settings, i)?;
+    let mut gnuplot = spawn_gnuplot(settings)?;
+    {
+        let gnuplot_stdin = gnuplot.stdin.as_mut().expect("failed to get piped stdin");
+        let mut writer = BufWriter::new(gnuplot_stdin);
+        write_gnuplot_script(&mut writer, settings, i)?;
+    }
+    let output = gnuplot.wait_with_output()?;
+    Ok(output.status.code())
+}
+
+/// Prints the points of frame `i` as text sized by `COLUMNS` and `LINES`, or
+/// 80 by 24 characters
+fn print_ascii_preview(settings: &PlotSettings, i: usize) -> Result<(), ViewerError> {
+    let points = read_frame_points(settings, i)?;
+    let size = |name: &str, default: usize| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let width = size("COLUMNS", 80).saturating_sub(2).max(10);
+    let height = size("LINES", 24).saturating_sub(4).max(5);
+    let (lines, outside) = preview::render(
+        &points,
+        &settings.min_bounds,
+        &settings.max_bounds,
+        width,
+        height,
+    );
+    println!(
+        "frame {}: {} points, {} outside the bounds",
+        i,
+        points.len(),
+        outside
+    );
+    for line in lines {
+        println!("{}", line);
+    }
+    if settings.min_bounds.len() == 1 {
+        println!(
+            "x: point index, y [{}:{}]",
+            settings.min_bounds[0], settings.max_bounds[0]
+        );
+    } else {
+        println!(
+            "x [{}:{}], y [{}:{}]",
+            settings.min_bounds[0],
+            settings.max_bounds[0],
+            settings.min_bounds[1],
+            settings.max_bounds[1]
+        );
+    }
+    Ok(())
+}
+
+/// Writes frame `i` to `path`, copying its image if it is rendered already
+fn write_poster(settings: &PlotSettings, i: usize, path: &Path) -> Result<(), ViewerError> {
+    if is_rendered(settings, i) {
+        fs::copy(frame_path(settings, i), path)?;
+        return Ok(());
+    }
+    check_sample_file(settings, i)?;
+    let mut gnuplot = spawn_gnuplot(settings)?;
+    {
+        let gnuplot_stdin = gnuplot.stdin.as_mut().expect("failed to get piped stdin");
+        let mut writer = BufWriter::new(gnuplot_stdin);
+        write_gnuplot_header(&mut writer, settings)?;
+        writeln!(writer, "set output {:?}", path)?;
+        write_gnuplot_panels(&mut writer, settings, i)?;
+    }
+    let status = gnuplot.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(ViewerError::Other(format!(
+            "poster frame {} failed with gnuplot status {:?}",
+            i,
+            status.code()
+        )))
+    }
+}
+
+/// Renders frames taken from `queue` with a single long-lived gnuplot process
+/// until the queue is empty
+///
+/// The exit status of gnuplot is only known once it exits, so results of all
+/// frames fed to it are sent through `tx` at that time. If gnuplot dies early,
+/// the frame being written is reported as an error and the remaining frames are
+/// left in the queue for other workers.
+fn render_frames_reusing_gnuplot(
+    settings: &PlotSettings,
+    queue: &sync::Mutex<VecDeque<usize>>,
+    tx: &sync::mpsc::Sender<FrameResult>,
+) {
+    let start = Instant::now();
+    let send = |i, result, time| {
+        tx.send((i, result, time))
+            .expect("failed to send item through channel tx")
+    };
+    let next_frame = || queue.lock().expect("frame queue poisoned").pop_front();
+    let mut gnuplot = match spawn_gnuplot(settings) {
+        Ok(gnuplot) => gnuplot,
+        Err(e) => {
+            // report through the first frame this worker would have rendered
+            if let Some(i) = next_frame() {
+                send(i, Err(e), Duration::ZERO);
+            }
+            return;
+        },
+    };
+    let mut rendered = Vec::new();
+    let mut failed = None;
+    {
+        let gnuplot_stdin = gnuplot.stdin.as_mut().expect("failed to get piped stdin");
+        let mut writer = BufWriter::new(gnuplot_stdin);
+        if let Err(e) = write_gnuplot_header(&mut writer, settings) {
+            failed = next_frame().map(|i| (i, e));
+        } else {
+            while let Some(i) = next_frame() {
+                if interrupt::is_interrupted() {
+                    send(i, Err(ViewerError::Interrupted), Duration::ZERO);
+                    continue;
+                }
+                if let Err(e) = check_sample_file(settings, i) {
+                    send(i, Err(e), Duration::ZERO);
+                    continue;
+                }
+                let written = emit_gnuplot_script(settings, i)
+                    .and_then(|_| write_gnuplot_frame(&mut writer, settings, i))
+                    .and_then(|_| writer.flush());
+                match written {
+                    Ok(()) => rendered.push(i),
+                    Err(e) => {
+                        failed = Some((i, e));
+                        break;
+                    },
+                }
+            }
+        }
+    }
+    // close stdin so gnuplot exits after the last frame
+    drop(gnuplot.stdin.take());
+    let status = match gnuplot.wait() {
+        Ok(status) => status.code(),
+        Err(e) => {
+            for i in rendered {
+                send(
+                    i,
+                    Err(ViewerError::Other(format!(
+                        "failed to wait for gnuplot: {}",
+                        e
+                    ))),
+                    Duration::ZERO,
+                );
+            }
+            return;
+        },
+    };
+    // frames share one process, so each is accounted an equal share of it
+    let time = start.elapsed() / rendered.len().max(1) as u32;
+    for i in rendered {
+        send(i, Ok(status), time);
+    }
+    if let Some((i, e)) = failed {
+        send(
+            i,
+            Err(ViewerError::Other(format!(
+                "gnuplot exited with status {:?} while rendering: {}",
+                status, e
+            ))),
+            Duration::ZERO,
+        );
+    }
+}
+
+/// Path of the sample file of frame `i`, `{i}.dat` or, if only that exists,
+/// the gzip compressed `{i}.dat.gz`
+fn sample_path(directory: &Path, i: usize) -> PathBuf {
+    data_path(directory, &i.to_string())
+}
+
+/// Path of `{stem}.dat`, falling back to `{stem}.dat.gz` if only that exists
+fn data_path(directory: &Path, stem: &str) -> PathBuf {
+    let path = directory.join(format!("{}.dat", stem));
+    let compressed = directory.join(format!("{}.dat.gz", stem));
+    if !path.exists() && compressed.exists() {
+        compressed
+    } else {
+        path
+    }
+}
+
+/// Data files of sample `i`, `{i}.NAME.dat` per species or just `{i}.dat`
+fn sample_paths(directory: &Path, i: usize, species: &[Species]) -> Vec<PathBuf> {
+    if species.is_empty() {
+        vec![sample_path(directory, i)]
+    } else {
+        species
+            .iter()
+            .map(|s| data_path(directory, &format!("{}.{}", i, s.name)))
+            .collect()
+    }
+}
+
+/// Samples of `samples` with a data file missing in `directory`, in order
+fn find_missing_samples(
+    directory: &Path,
+    samples: &RangeInclusive<usize>,
+    species: &[Species],
+) -> io::Result<Vec<usize>> {
+    let mut present = HashSet::new();
+    for entry in fs::read_dir(directory)? {
+        present.insert(entry?.file_name());
+    }
+    let has = |stem: String| {
+        present.contains(OsStr::new(&format!("{}.dat", stem)))
+            || present.contains(OsStr::new(&format!("{}.dat.gz", stem)))
+    };
+    Ok(samples
+        .clone()
+        .filter(|i| {
+            if species.is_empty() {
+                !has(i.to_string())
+            } else {
+                species.iter().any(|s| !has(format!("{}.{}", i, s.name)))
+            }
+        })
+        .collect())
+}
+
+/// Lists sorted numbers compactly, with runs written as `START:END`, e.g.
+/// `3, 7:9`
+fn format_ranges(numbers: &[usize]) -> String {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &n in numbers {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == n => *end = n,
+            _ => runs.push((n, n)),
+        }
+    }
+    runs.into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}:{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Data files of sample `i` paired with the species they are plotted as
+fn sample_sources(settings: &PlotSettings, i: usize) -> Vec<(PathBuf, Option<&Species>)> {
+    let record = settings.records.as_ref().and_then(|records| {
+        records
+            .read()
+            .expect("manifest lock poisoned")
+            .get(&i)
+            .cloned()
+    });
+    if let Some(record) = record {
+        vec![(record.path, None)]
+    } else if let Some(ref file) = settings.indexed_file {
+        vec![(file.clone(), None)]
+    } else if settings.species.is_empty() {
+        vec![(sample_path(&settings.directory, i), None)]
+    } else {
+        sample_paths(&settings.directory, i, &settings.species)
+            .into_iter()
+            .zip(settings.species.iter().map(Some))
+            .collect()
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "gz")
+}
+
+/// Returns the file name of a plot clause, `'-'` if the data is inlined
+fn plot_source(settings: &PlotSettings, path: &Path) -> String {
+    if settings.inline_data {
+        "'-'".to_owned()
+    } else {
+        gnuplot_source(path)
+    }
+}
+
+/// Quotes a data file for gnuplot, decompressing gzip files on the fly
+/// through a pipe so no temporary files are needed; the path is quoted for
+/// the shell running the pipe
+fn gnuplot_source(path: &Path) -> String {
+    if is_gzip(path) {
+        format!("{:?}", format!("< gzip -dc {}", concat_quote(path)))
+    } else {
+        format!("{:?}", path)
+    }
+}
+
+/// Path of the image file of frame `i`
+fn frame_path(settings: &PlotSettings, i: usize) -> PathBuf {
+    settings
+        .frames_directory
+        .join(format!("{}.{}", i, settings.terminal.extension()))
+}
+
+fn script_path(settings: &PlotSettings, i: usize) -> PathBuf {
+    settings.directory.join(format!("{}.gp", i))
+}
+
+/// Checks whether the image of frame `i` exists and is newer than all data
+/// files of sample `i`
+fn is_rendered(settings: &PlotSettings, i: usize) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match modified(&frame_path(settings, i)) {
+        Some(png) => sample_sources(settings, i)
+            .iter()
+            .all(|(path, _)| modified(path).is_some_and(|dat| png > dat)),
+        None => false,
+    }
+}
+
+/// Reads the non-empty, non-comment lines of a data file
+///
+/// Gzip compressed files are decompressed with `gzip -dc`.
+fn read_data_lines(path: &Path) -> Result<Vec<String>, io::Error> {
+    let lines = if is_gzip(path) {
+        let output = Command::new("gzip")
+            .arg("-dc")
+            .arg(path)
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to decompress {:?}", path),
+            ));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    } else {
+        let file = File::open(path)?;
+        BufReader::new(file)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    Ok(lines
+        .into_iter()
+        .filter(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .collect())
+}
+
+/// Reads the coordinates of every point plotted in frame `i`
+fn read_frame_points(settings: &PlotSettings, i: usize) -> Result<Vec<Vec<f64>>, ViewerError> {
+    // the z axis of pseudo 3D plots is not in the data
+    let dimension = settings.min_bounds.len() - settings.pseudo_3d as usize;
+    let columns = settings
+        .columns
+        .clone()
+        .unwrap_or_else(|| (1..=dimension).collect());
+    let mut points = Vec::new();
+    for (path, _) in sample_sources(settings, i) {
+        points.extend(read_points(&path, &columns, settings.separator)?);
+    }
+    Ok(points)
+}
+
+/// Reads the given 1-based coordinate columns of every point in a sample file
+fn read_points(
+    path: &Path,
+    columns: &[usize],
+    separator: Option<char>,
+) -> Result<Vec<Vec<f64>>, ViewerError> {
+    read_data_lines(path)?
+        .iter()
+        .map(|line| {
+            let fields = split_fields(line, separator);
+            columns
+                .iter()
+                .map(|&c| match fields.get(c - 1) {
+                    Some(field) => Ok(field.parse()?),
+                    None => Err(ViewerError::Other(format!(
+                        "{:?}: column {} missing in line {:?}",
+                        path, c, line
+                    ))),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Bounds like `compute_bounds`, read from `_bounds_auto.dat` if it was written
+/// with the same arguments after all sample files were last modified
+///
+/// Freshly computed bounds are written to the cache, also with `recompute`.
+fn cached_bounds(
+    directory: &Path,
+    samples: RangeInclusive<usize>,
+    species: &[Species],
+    padding: f64,
+    separator: Option<char>,
+    columns: &[usize],
+    recompute: bool,
+) -> Result<(Vec<f64>, Vec<f64>), ViewerError> {
+    let cache = directory.join("_bounds_auto.dat");
+    // the arguments the cached bounds depend on, every one that changes the
+    // points read from the sample files
+    let header = format!(
+        "# auto bounds of samples {}:{}, padding {}, columns {}, separator {}, species {}",
+        samples.start(),
+        samples.end(),
+        padding,
+        columns
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        separator.map_or("whitespace".to_owned(), |c| format!("{:?}", c)),
+        if species.is_empty() {
+            "none".to_owned()
+        } else {
+            species
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+    );
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let fresh = !recompute
+        && modified(&cache).is_some_and(|cached| {
+            samples.clone().all(|i| {
+                sample_paths(directory, i, species)
+                    .iter()
+                    .all(|path| modified(path).is_some_and(|dat| cached > dat))
+            })
+        });
+    if fresh {
+        let content = fs::read_to_string(&cache)?;
+        let mut lines = content.lines();
+        if lines.next() == Some(header.as_str()) {
+            if let (Some(min_bounds), Some(max_bounds)) = (lines.next(), lines.next()) {
+                info!("auto bounds read from {:?}", cache);
+                return Ok((
+                    read_bounds(min_bounds, None)?,
+                    read_bounds(max_bounds, None)?,
+                ));
+            }
+        }
+    }
+    let (min_bounds, max_bounds) =
+        compute_bounds(directory, samples, species, padding, separator, columns)?;
+    let join = |bounds: &[f64]| {
+        bounds
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    fs::write(
+        &cache,
+        format!("{}\n{}\n{}\n", header, join(&min_bounds), join(&max_bounds)),
+    )?;
+    Ok((min_bounds, max_bounds))
+}
+
+/// Scans the given 1-based coordinate `columns` of the sample files of
+/// `samples` for per axis min and max, then expands each axis by `padding`
+/// times its extent on both sides
+///
+/// Missing sample files are skipped, but some point must be found.
+pub fn compute_bounds(
+    directory: &Path,
+    samples: RangeInclusive<usize>,
+    species: &[Species],
+    padding: f64,
+    separator: Option<char>,
+    columns: &[usize],
+) -> Result<(Vec<f64>, Vec<f64>), ViewerError> {
+    let dimension = columns.len();
+    let mut min_bounds = vec![f64::INFINITY; dimension];
+    let mut max_bounds = vec![f64::NEG_INFINITY; dimension];
+    let mut point_number = 0;
+    for i in samples.clone() {
+        // samples skipped under --keep-going have no files
+        for path in sample_paths(directory, i, species) {
+            if !path.exists() {
+                continue;
+            }
+            for point in read_points(&path, columns, separator)? {
+                for d in 0..dimension {
+                    min_bounds[d] = min_bounds[d].min(point[d]);
+                    max_bounds[d] = max_bounds[d].max(point[d]);
+                }
+                point_number += 1;
+            }
+        }
+    }
+    if point_number == 0 {
+        return Err(ViewerError::Other(format!(
+            "no point in samples {}:{} of {:?} to compute bounds from",
+            samples.start(),
+            samples.end(),
+            directory
+        )));
+    }
+    for d in 0..dimension {
+        let extent = max_bounds[d] - min_bounds[d];
+        min_bounds[d] -= extent * padding;
+        max_bounds[d] += extent * padding;
+    }
+    info!("auto bounds: {:?} {:?}", min_bounds, max_bounds);
+    Ok((min_bounds, max_bounds))
+}
+
+/// Columns bounds are computed from: `columns` if set, else the first
+/// `dimension` ones, else one per axis of the bounds of `_meta.json` or
+/// `_bounds.dat`
+fn bounds_columns(
+    directory: &Path,
+    columns: Option<&[usize]>,
+    dimension: Option<usize>,
+    meta_bounds: Option<&Bounds>,
+    separator: Option<char>,
+) -> Result<Vec<usize>, ViewerError> {
+    let dimension = match (columns, dimension, meta_bounds) {
+        (Some(columns), _, _) => return Ok(columns.to_vec()),
+        (None, Some(dimension), _) => dimension,
+        (None, None, Some((min_bounds, _))) => min_bounds.len(),
+        (None, None, None) if directory.join("_bounds.dat").is_file() => {
+            read_bounds_file(directory, separator)?.0.len()
+        },
+        (None, None, None) => {
+            return Err(ViewerError::Other(format!(
+                "dimension of {:?} unknown without _meta.json bounds or _bounds.dat, pass \
+                 --dimension or --columns",
+                directory
+            )))
+        },
+    };
+    Ok((1..=dimension).collect())
+}
+
+/// Parses a `A:B` pair of values
+fn read_pair<T>(s: &str) -> Result<(T, T), ViewerError>
+where
+    T: FromStr,
+    ViewerError: From<T::Err>,
+{
+    let mut parts = s.splitn(2, ':');
+    let a = parts.next().unwrap_or_default().trim().parse()?;
+    let b = parts
+        .next()
+        .ok_or_else(|| ViewerError::Other(format!("{:?} is not of the form A:B", s)))?
+        .trim()
+        .parse()?;
+    Ok((a, b))
+}
+
+/// Counts the data lines of the sample files of `frames` and describes the
+/// first frame whose counts differ from the frame before it
+fn find_inconsistent_frame(
+    directory: &Path,
+    frames: &[usize],
+    species: &[Species],
+) -> Result<Option<String>, ViewerError> {
+    let mut previous: Option<(usize, Vec<usize>)> = None;
+    for &i in frames {
+        let paths = sample_paths(directory, i, species);
+        let counts = paths
+            .iter()
+            .map(|path| read_data_lines(path).map(|lines| lines.len()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some((j, previous_counts)) = &previous {
+            if let Some(k) = (0..counts.len()).find(|&k| counts[k] != previous_counts[k]) {
+                return Ok(Some(format!(
+                    "frame {} has {} data lines in {:?} but frame {} had {}",
+                    i, counts[k], paths[k], j, previous_counts[k]
+                )));
+            }
+        }
+        previous = Some((i, counts));
+    }
+    Ok(None)
+}
+
+/// Computes the centroid of the points of each of `frames`, averaged over the
+/// `smoothing` frames before and after it to avoid jitter
+///
+/// Frames without points take the centroid of the frame before them.
+fn compute_centers(
+    directory: &Path,
+    frames: &[usize],
+    species: &[Species],
+    columns: &[usize],
+    separator: Option<char>,
+    smoothing: usize,
+) -> Result<HashMap<usize, Vec<f64>>, ViewerError> {
+    let mut centroids: Vec<Vec<f64>> = Vec::with_capacity(frames.len());
+    for &i in frames {
+        let mut sum = vec![0.0; columns.len()];
+        let mut count = 0;
+        for path in sample_paths(directory, i, species) {
+            for point in read_points(&path, columns, separator)? {
+                for (s, x) in sum.iter_mut().zip(point) {
+                    *s += x;
+                }
+                count += 1;
+            }
+        }
+        let centroid = if count > 0 {
+            sum.into_iter().map(|s| s / count as f64).collect()
+        } else if let Some(previous) = centroids.last() {
+            previous.clone()
+        } else {
+            return Err(ViewerError::Other(format!(
+                "sample {} contains no points to follow",
+                i
+            )));
+        };
+        centroids.push(centroid);
+    }
+    Ok(frames
+        .iter()
+        .enumerate()
+        .map(|(k, &i)| {
+            let window =
+                &centroids[k.saturating_sub(smoothing)..(k + smoothing + 1).min(frames.len())];
+            let center = (0..columns.len())
+                .map(|d| window.iter().map(|c| c[d]).sum::<f64>() / window.len() as f64)
+                .collect();
+            (i, center)
+        })
+        .collect())
+}
+
+/// Computes the padded bounds of the points of each of `frames`, averaged per
+/// axis over the `smoothing` frames before and after it like the centroids of
+/// `compute_centers`
+fn compute_frame_bounds(
+    directory: &Path,
+    frames: &[usize],
+    species: &[Species],
+    padding: f64,
+    columns: &[usize],
+    separator: Option<char>,
+    smoothing: usize,
+) -> Result<HashMap<usize, Bounds>, ViewerError> {
+    let bounds = frames
+        .iter()
+        .map(|&i| compute_bounds(directory, i..=i, species, padding, separator, columns))
+        .collect::<Result<Vec<_>, _>>()?;
+    let average = |window: &[Bounds], d: usize| {
+        let n = window.len() as f64;
+        (
+            window.iter().map(|b| b.0[d]).sum::<f64>() / n,
+            window.iter().map(|b| b.1[d]).sum::<f64>() / n,
+        )
+    };
+    Ok(frames
+        .iter()
+        .enumerate()
+        .map(|(k, &i)| {
+            let window =
+                &bounds[k.saturating_sub(smoothing)..(k + smoothing + 1).min(frames.len())];
+            let (min_bounds, max_bounds) = (0..columns.len()).map(|d| average(window, d)).unzip();
+            (i, (min_bounds, max_bounds))
+        })
+        .collect())
+}
+
+/// Scans the min and max of a 1-based data column over the sample files of
+/// `frames`
+fn scan_column_range(
+    directory: &Path,
+    frames: RangeInclusive<usize>,
+    species: &[Species],
+    column: usize,
+    separator: Option<char>,
+) -> Result<(f64, f64), ViewerError> {
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    for path in frames.flat_map(|i| sample_paths(directory, i, species)) {
+        for line in read_data_lines(&path)? {
+            let value: f64 = split_fields(&line, separator)
+                .get(column - 1)
+                .ok_or_else(|| {
+                    ViewerError::Other(format!(
+                        "{:?}: column {} missing in {:?}",
+                        path, column, line
+                    ))
+                })?
+                .parse()?;
+            min = min.min(value);
+            max = max.max(value);
+        }
+    }
+    if min <= max {
+        Ok((min, max))
+    } else {
+        Err(ViewerError::Other(
+            "no data to scan color range from".to_owned(),
+        ))
+    }
+}
+
+/// Checks that `axes` names plotted data axes whose min bounds are positive
+///
+/// 1D data is plotted on the y axis against the point index.
+fn check_log_scale(axes: &str, min_bounds: &[f64]) -> Result<(), ViewerError> {
+    let data_axes: &[char] = match min_bounds.len() {
+        1 => &['y'],
+        2 => &['x', 'y'],
+        _ => &['x', 'y', 'z'],
+    };
+    if axes.is_empty() {
+        return Err(ViewerError::Other("no log scale axes given".to_owned()));
+    }
+    for axis in axes.chars() {
+        let d = data_axes.iter().position(|&a| a == axis).ok_or_else(|| {
+            ViewerError::Other(format!(
+                "log scale axis {:?} is not one of the data axes {:?}",
+                axis, data_axes
+            ))
+        })?;
+        if min_bounds[d] <= 0.0 {
+            return Err(ViewerError::Other(format!(
+                "min bound {} of the {} axis must be positive in log scale",
+                min_bounds[d], axis
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Parses `X:Y` or `X:Y:Z` axis ratios
+fn read_aspect(s: &str) -> Result<Vec<f64>, ViewerError> {
+    Ok(s.split(':')
+        .map(|r| r.trim().parse())
+        .collect::<Result<Vec<f64>, _>>()?)
+}
+
+/// Checks the axis ratios of `--aspect` fit data of `dimension`
+///
+/// In 3D gnuplot can only scale z independently, so x and y must be equal.
+fn check_aspect(aspect: &[f64], dimension: usize) -> Result<(), ViewerError> {
+    let s = aspect
+        .iter()
+        .map(f64::to_string)
+        .collect::<Vec<_>>()
+        .join(":");
+    if aspect.len() != dimension {
+        Err(ViewerError::Other(format!(
+            "aspect {:?} has {} ratios, expected {}",
+            s,
+            aspect.len(),
+            dimension
+        )))
+    } else if aspect.iter().any(|&r| r <= 0.0) {
+        Err(ViewerError::Other(format!(
+            "aspect {:?} must be positive",
+            s
+        )))
+    } else if dimension == 3 && aspect[0] != aspect[1] {
+        Err(ViewerError::Other(format!(
+            "aspect {:?}: x and y ratios must be equal in 3D",
+            s
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks a `START:END` frame range lies within the samples
+fn check_frame_range(
+    (start, end): (usize, usize),
+    samples: &RangeInclusive<usize>,
+) -> Result<(usize, usize), ViewerError> {
+    if samples.start() <= &start && start <= end && end <= *samples.end() {
+        Ok((start, end))
+    } else {
+        Err(ViewerError::Other(format!(
+            "invalid frame range {}:{}, expected {} <= START <= END <= {}",
+            start,
+            end,
+            samples.start(),
+            samples.end()
+        )))
+    }
+}
+
+/// Frames whose time, `time_step` per frame, lies within `since` and `until`,
+/// both optional and checked to lie within the times of the samples
+fn read_time_window(
+    since: Option<f64>,
+    until: Option<f64>,
+    time_step: f64,
+    samples: &RangeInclusive<usize>,
+) -> Result<(usize, usize), ViewerError> {
+    let (start, end) = (
+        *samples.start() as f64 * time_step,
+        *samples.end() as f64 * time_step,
+    );
+    for time in since.iter().chain(&until) {
+        if !(start..=end).contains(time) {
+            return Err(ViewerError::Other(format!(
+                "time {} outside the samples, expected {} <= TIME <= {}",
+                time, start, end
+            )));
+        }
+    }
+    // a little slack keeps times that are a frame time up to rounding
+    const SLACK: f64 = 1e-9;
+    let first = match since {
+        Some(since) => (since / time_step - SLACK).ceil() as usize,
+        None => *samples.start(),
+    };
+    let last = match until {
+        Some(until) => (until / time_step + SLACK).floor() as usize,
+        None => *samples.end(),
+    };
+    if first > last {
+        return Err(ViewerError::Other(format!(
+            "no frame between times {} and {}",
+            since.unwrap_or(start),
+            until.unwrap_or(end)
+        )));
+    }
+    Ok((first, last))
+}
+
+/// Parses a `--watch-timeout` in seconds, which must be finite and not negative
+fn read_timeout(s: &str) -> Result<Duration, ViewerError> {
+    let seconds: f64 = s.parse()?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        ViewerError::Other(format!(
+            "watch timeout {} must be a finite number of seconds, at least 0",
+            seconds
+        ))
+    })
+}
+
+/// Parses comma separated 1-based column indices
+fn read_columns(s: &str) -> Result<Vec<usize>, ViewerError> {
+    let columns = s
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<Vec<usize>, _>>()?;
+    if columns.contains(&0) {
+        return Err(ViewerError::Other("columns are 1-based".to_owned()));
+    }
+    Ok(columns)
+}
+
+/// Splits a data line into fields, on whitespace if `separator` is unset
+fn split_fields(line: &str, separator: Option<char>) -> Vec<&str> {
+    match separator {
+        Some(separator) => line.split(separator).map(str::trim).collect(),
+        None => line.split_whitespace().collect(),
+    }
+}
+
+/// Parses a comma separated list of integer point types
+fn read_point_types(s: &str) -> Result<Vec<String>, ViewerError> {
+    s.split(',')
+        .map(str::trim)
+        .map(|point_type| match point_type.parse::<u32>() {
+            Ok(_) => Ok(point_type.to_owned()),
+            Err(_) => Err(ViewerError::Other(format!(
+                "point type {:?} is not a non-negative integer",
+                point_type
+            ))),
+        })
+        .collect()
+}
+
+/// Parses sizes separated by `;`, or sizes written as `WxH` separated by `,`,
+/// e.g. `1920x1080,1280x720,1080x1080`
+fn read_sizes(sizes: &str) -> Result<Vec<(f64, f64)>, ViewerError> {
+    if sizes.contains(';') {
+        sizes.split(';').map(read_size).collect()
+    } else if sizes.contains('x') {
+        sizes.split(',').map(read_size).collect()
+    } else {
+        Ok(vec![read_size(sizes)?])
+    }
+}
+
+/// Parses a `W,H` or `WxH` size of positive integers, or `N` for a square
+fn read_size(size: &str) -> Result<(f64, f64), ViewerError> {
+    let invalid = || {
+        ViewerError::Other(format!(
+            "size {:?} is not of the form W,H, WxH or N with positive integers, e.g. 1920,1080",
+            size
+        ))
+    };
+    let parts = size
+        .split([',', 'x'])
+        .map(|part| part.trim().parse::<u32>().ok().filter(|&n| n > 0))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    match parts[..] {
+        [n] => Ok((f64::from(n), f64::from(n))),
+        [width, height] => Ok((f64::from(width), f64::from(height))),
+        _ => Err(invalid()),
+    }
+}
+
+/// Roughly estimates the memory in bytes one gnuplot process needs for frame
+/// `i` of `size` pixels
+///
+/// Besides a fixed base, gnuplot holds the image and the parsed points of all
+/// data files of the frame including its trails, taken as four times their
+/// file size.
+fn estimate_gnuplot_memory(settings: &PlotSettings, size: (f64, f64), i: usize) -> f64 {
+    const BASE: f64 = 32.0 * 1024.0 * 1024.0;
+    let mut data: u64 = sample_sources(settings, i)
+        .iter()
+        .filter_map(|(path, _)| fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+    // gnuplot keeps only the block of the frame
+    if settings.indexed_file.is_some() {
+        data /= settings.sample_number as u64 + 1;
+    }
+    BASE + size.0 * size.1 * 4.0 + (data * 4 * (settings.trail_length as u64 + 1)) as f64
+}
+
+/// Contents of the optional `_meta.json` of a data directory
+#[derive(Debug)]
+pub struct Meta {
+    pub sample_number: usize,
+    pub sample_time: f64,
+    /// Min and max bounds, absent when the file leaves them to `_bounds.dat`
+    pub bounds: Option<(Vec<f64>, Vec<f64>)>,
+}
+
+/// Reads the sample number, sample time and possibly the bounds of a data
+/// directory from `_meta.json`, or else from `_sample.txt` and `_time.txt`
+pub fn read_samples(directory: &Path) -> Result<Meta, ViewerError> {
+    let meta_path = directory.join("_meta.json");
+    if meta_path.is_file() {
+        info!("reading {:?}", meta_path);
+        return read_meta(&meta_path);
+    }
+    Ok(Meta {
+        sample_number: fs::read_to_string(directory.join("_sample.txt"))?
+            .trim()
+            .parse()?,
+        sample_time: fs::read_to_string(directory.join("_time.txt"))?
+            .trim()
+            .parse()?,
+        bounds: None,
+    })
+}
+
+/// Logs where the bounds of `directory` came from and their range per axis
+fn log_bounds(directory: &Path, source: &str, min_bounds: &[f64], max_bounds: &[f64]) {
+    info!("bounds of {:?} {}", directory, source);
+    for ((axis, min), max) in ["x", "y", "z"].iter().zip(min_bounds).zip(max_bounds) {
+        info!("{} range: [{}:{}]", axis, min, max);
+    }
+}
+
+/// Settings of a `--mosaic` panel drawing `directory` like `settings`
+///
+/// Forced bounds are kept, otherwise the bounds of the panel are computed with
+/// the `auto_bounds` padding, from frame `fit_frame` only if set, or read from
+/// its directory.
+fn panel_settings(
+    settings: &PlotSettings,
+    directory: &Path,
+    forced_bounds: bool,
+    auto_bounds: Option<f64>,
+    fit_frame: Option<usize>,
+    recompute: bool,
+    dimension: Option<usize>,
+) -> Result<PlotSettings, ViewerError> {
+    let meta = read_samples(directory)?;
+    let samples = settings.sample_start..=settings.sample_start + meta.sample_number;
+    let (source, origin, (min_bounds, max_bounds)) = if forced_bounds {
+        (
+            "set by --min-bounds and --max-bounds",
+            "--min-bounds and --max-bounds".to_owned(),
+            (settings.min_bounds.clone(), settings.max_bounds.clone()),
+        )
+    } else if let Some(padding) = auto_bounds {
+        let columns = bounds_columns(
+            directory,
+            settings.columns.as_deref(),
+            dimension,
+            meta.bounds.as_ref(),
+            settings.separator,
+        )?;
+        let bounds = match fit_frame {
+            Some(frame) => compute_bounds(
+                directory,
+                frame..=frame,
+                &settings.species,
+                padding,
+                settings.separator,
+                &columns,
+            )?,
+            None => cached_bounds(
+                directory,
+                samples,
+                &settings.species,
+                padding,
+                settings.separator,
+                &columns,
+                recompute,
+            )?,
+        };
+        (
+            "computed from the samples",
+            format!("the samples of {:?}", directory),
+            bounds,
+        )
+    } else if let Some(bounds) = meta.bounds {
+        (
+            "read from _meta.json",
+            format!("{:?}", directory.join("_meta.json")),
+            bounds,
+        )
+    } else {
+        (
+            "read from _bounds.dat",
+            format!("{:?}", directory.join("_bounds.dat")),
+            read_bounds_file(directory, settings.separator)?,
+        )
+    };
+    let (min_bounds, max_bounds) = select_dimension(&origin, min_bounds, max_bounds, dimension)?;
+    log_bounds(directory, source, &min_bounds, &max_bounds);
+    Ok(PlotSettings {
+        directory: directory.to_owned(),
+        sample_time: meta.sample_time,
+        sample_number: meta.sample_number,
+        min_bounds,
+        max_bounds,
+        panels: Vec::new(),
+        centers: HashMap::new(),
+        frame_bounds: HashMap::new(),
+        ..settings.clone()
+    })
+}
+
+/// Checks the min and max bounds read from `origin` have as many axes, and
+/// keeps the first `dimension` of them, ignoring extra columns
+///
+/// Without `dimension`, every axis is plotted, so there must be 1 to 3.
+fn select_dimension(
+    origin: &str,
+    mut min_bounds: Vec<f64>,
+    mut max_bounds: Vec<f64>,
+    dimension: Option<usize>,
+) -> Result<(Vec<f64>, Vec<f64>), ViewerError> {
+    if min_bounds.len() != max_bounds.len() {
+        return Err(ViewerError::Other(format!(
+            "{}: {} min bounds but {} max bounds given",
+            origin,
+            min_bounds.len(),
+            max_bounds.len()
+        )));
+    }
+    match dimension {
+        Some(dimension) if min_bounds.len() < dimension => Err(ViewerError::Other(format!(
+            "{}: {} bounds given for dimension {}",
+            origin,
+            min_bounds.len(),
+            dimension
+        ))),
+        Some(dimension) => {
+            min_bounds.truncate(dimension);
+            max_bounds.truncate(dimension);
+            Ok((min_bounds, max_bounds))
+        },
+        None if !(1..=3).contains(&min_bounds.len()) => Err(ViewerError::Other(format!(
+            "{}: {} bounds given, expected 1 to 3, pass --dimension to plot the first axes",
+            origin,
+            min_bounds.len()
+        ))),
+        None => Ok((min_bounds, max_bounds)),
+    }
+}
+
+/// Reads the min and max bounds lines of `_bounds.dat`
+pub fn read_bounds_file(
+    directory: &Path,
+    separator: Option<char>,
+) -> Result<(Vec<f64>, Vec<f64>), ViewerError> {
+    let mut bounds = read_data_lines(&directory.join("_bounds.dat"))?.into_iter();
+    let min_bounds: Vec<f64> = read_bounds(
+        &bounds
+            .next()
+            .ok_or_else(|| ViewerError::Other("min bounds line missing".to_owned()))?,
+        separator,
+    )?;
+    let max_bounds: Vec<f64> = read_bounds(
+        &bounds
+            .next()
+            .ok_or_else(|| ViewerError::Other("max bounds line missing".to_owned()))?,
+        separator,
+    )?;
+    Ok((min_bounds, max_bounds))
+}
+
+/// Reads `_meta.json`, which replaces `_sample.txt`, `_time.txt` and
+/// `_bounds.dat`, e.g.
+///
+/// ```json
+/// {"sample_number": 100, "sample_time": 0.01, "dimension": 3,
+///  "bounds": {"min": [-1, -1, -1], "max": [1, 1, 1]}}
+/// ```
+///
+/// `bounds` may also be given as `[[min...], [max...]]`.
+pub fn read_meta(path: &Path) -> Result<Meta, ViewerError> {
+    parse_meta(&fs::read_to_string(path)?)
+        .map_err(|e| ViewerError::Other(format!("{:?}: {}", path, e)))
+}
+
+/// Parses the contents of a `_meta.json`, see `read_meta`
+fn parse_meta(s: &str) -> Result<Meta, String> {
+    let value: json::Value = s.parse()?;
+    let sample_number = value
+        .get("sample_number")
+        .and_then(json::Value::as_usize)
+        .ok_or_else(|| "sample_number must be a non-negative integer".to_owned())?;
+    let sample_time = value
+        .get("sample_time")
+        .and_then(json::Value::as_f64)
+        .ok_or_else(|| "sample_time must be a number".to_owned())?;
+    let numbers = |value: Option<&json::Value>, name: &str| -> Result<Vec<f64>, String> {
+        value
+            .and_then(json::Value::as_array)
+            .and_then(|values| values.iter().map(json::Value::as_f64).collect())
+            .ok_or_else(|| format!("{} bounds must be an array of numbers", name))
+    };
+    let bounds = match value.get("bounds") {
+        None | Some(json::Value::Null) => None,
+        Some(bounds @ json::Value::Object(_)) => Some((
+            numbers(bounds.get("min"), "min")?,
+            numbers(bounds.get("max"), "max")?,
+        )),
+        Some(json::Value::Array(bounds)) if bounds.len() == 2 => Some((
+            numbers(bounds.first(), "min")?,
+            numbers(bounds.get(1), "max")?,
+        )),
+        Some(_) => return Err("bounds must be {\"min\": [...], \"max\": [...]}".to_owned()),
+    };
+    if let Some((min_bounds, max_bounds)) = &bounds {
+        if min_bounds.len() != max_bounds.len() {
+            return Err("min and max bounds differ in dimension".to_owned());
+        }
+    }
+    match value.get("dimension") {
+        None | Some(json::Value::Null) => (),
+        Some(dimension) => {
+            let dimension = dimension
+                .as_usize()
+                .ok_or_else(|| "dimension must be a non-negative integer".to_owned())?;
+            if let Some((min_bounds, _)) = &bounds {
+                if min_bounds.len() != dimension {
+                    return Err(format!(
+                        "dimension {} does not match {} bounds",
+                        dimension,
+                        min_bounds.len()
+                    ));
+                }
+            }
+        },
+    }
+    Ok(Meta {
+        sample_number,
+        sample_time,
+        bounds,
+    })
+}
+
+/// Reads `frame elevation azimuth zoom` keyframe lines of a camera path
+///
+/// Keyframes must be in increasing frame order within `samples`.
+fn read_camera(
+    path: &Path,
+    samples: &RangeInclusive<usize>,
+    separator: Option<char>,
+) -> Result<CameraPath, ViewerError> {
+    let mut keys: Vec<CameraKey> = Vec::new();
+    for line in read_data_lines(path)? {
+        let fields = split_fields(&line, separator);
+        if fields.len() != 4 {
+            return Err(ViewerError::Other(format!(
+                "{:?}: expected frame elevation azimuth zoom in line {:?}",
+                path, line
+            )));
+        }
+        let key = CameraKey {
+            frame: fields[0].parse()?,
+            elevation: fields[1].parse()?,
+            azimuth: fields[2].parse()?,
+            zoom: fields[3].parse()?,
+        };
+        if !samples.contains(&key.frame) {
+            return Err(ViewerError::Other(format!(
+                "{:?}: keyframe {} out of sample range {}:{}",
+                path,
+                key.frame,
+                samples.start(),
+                samples.end()
+            )));
+        }
+        if let Some(last) = keys.last() {
+            if key.frame <= last.frame {
+                return Err(ViewerError::Other(format!(
+                    "{:?}: keyframe {} does not follow keyframe {}",
+                    path, key.frame, last.frame
+                )));
+            }
+        }
+        keys.push(key);
+    }
+    if keys.is_empty() {
+        return Err(ViewerError::Other(format!(
+            "{:?} contains no keyframes",
+            path
+        )));
+    }
+    Ok(CameraPath { keys })
+}
+
+/// Parses a line of bounds, ignoring everything after a `#`
+///
+/// Without a separator any whitespace separates numbers, so tabs and the `\r`
+/// of CRLF line endings are fine. Empty lines give no bounds.
+pub fn read_bounds(s: &str, separator: Option<char>) -> Result<Vec<f64>, ParseFloatError> {
+    // a byte order mark is left at the start of files by some Windows editors
+    let s = s.trim_start_matches('\u{feff}');
+    let s = s.split('#').next().unwrap_or_default();
+    split_fields(s, separator)
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_bounds_strips_inline_comments() {
+        assert_eq!(read_bounds("0 0 0 # origin", None), Ok(vec![0.0, 0.0, 0.0]));
+        assert_eq!(read_bounds("-1 2.5#no space", None), Ok(vec![-1.0, 2.5]));
+        assert_eq!(read_bounds("# only a comment", None), Ok(vec![]));
+    }
+
+    #[test]
+    fn read_bounds_trims_whitespace() {
+        assert_eq!(read_bounds("  1  2   3  ", None), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(
+            read_bounds(" 1, 2 ,3 # c", Some(',')),
+            Ok(vec![1.0, 2.0, 3.0])
+        );
+    }
+
+    #[test]
+    fn read_bounds_splits_tabs() {
+        assert_eq!(read_bounds("1\t2\t3", None), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(read_bounds("\t-1 \t2\t# tabs", None), Ok(vec![-1.0, 2.0]));
+        assert_eq!(read_bounds("1\t2", Some('\t')), Ok(vec![1.0, 2.0]));
+    }
+
+    #[test]
+    fn read_bounds_reads_plain_lines() {
+        assert_eq!(read_bounds("1 2 3", None), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(read_bounds("-1.5 -2 -0", None), Ok(vec![-1.5, -2.0, -0.0]));
+        assert_eq!(
+            read_bounds("1e3 -2.5E-2 +4e+1", None),
+            Ok(vec![1000.0, -0.025, 40.0])
+        );
+    }
+
+    #[test]
+    fn read_bounds_handles_windows_files() {
+        assert_eq!(read_bounds("1 2 3\r", None), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(read_bounds("1,2,3\r", Some(',')), Ok(vec![1.0, 2.0, 3.0]));
+        assert_eq!(read_bounds("\u{feff}1 2", None), Ok(vec![1.0, 2.0]));
+    }
+
+    #[test]
+    fn read_bounds_accepts_empty_input() {
+        assert_eq!(read_bounds("", None), Ok(vec![]));
+        assert_eq!(read_bounds(" \t \r", None), Ok(vec![]));
+        assert_eq!(read_bounds("", Some(',')), Ok(vec![]));
+    }
+
+    #[test]
+    fn read_bounds_rejects_garbage() {
+        assert!(read_bounds("1 x 3", None).is_err());
+        assert!(read_bounds("1.2.3", None).is_err());
+        assert!(read_bounds("1 2,3", None).is_err());
+        assert!(read_bounds("--1", None).is_err());
+        assert!(matches!(
+            read_bounds("one", None).map_err(ViewerError::from),
+            Err(ViewerError::ParseFloat(_))
+        ));
+    }
+
+    #[test]
+    fn render_options_parse_flags() {
+        let options = RenderOptions::from_args([
+            "n-body-viewer",
+            "out",
+            "frames",
+            "--size",
+            "640,480",
+            "--stats-overlay",
+            "bottom-right",
+            "--frame-range",
+            "2:5",
+        ])
+        .unwrap();
+        assert_eq!(options.path, Path::new("out"));
+        assert_eq!(options.stage, Stage::Frames);
+        assert_eq!(options.sizes, vec![(640.0, 480.0)]);
+        assert_eq!(options.stats_overlay, Some(Corner::BottomRight));
+        assert_eq!(options.frame_range, Some((2, 5)));
+        assert_eq!(RenderOptions::default().stage, Stage::Render);
+        assert!(RenderOptions::from_args(["n-body-viewer", "--clock-overlay", "middle"]).is_err());
+    }
+
+    #[test]
+    fn palette_and_colorbar_need_a_colored_plot() {
+        let check = |args: &[&str]| {
+            let args = ["n-body-viewer"].iter().chain(args);
+            check_options(&RenderOptions::from_args(args).unwrap())
+        };
+        assert!(check(&["--heatmap", "--palette", "hot", "--colorbar"]).is_ok());
+        assert!(check(&["--color-column", "4", "--palette", "jet", "--colorbar"]).is_ok());
+        assert!(check(&["--palette", "hot"]).is_err());
+        assert!(check(&["--colorbar"]).is_err());
+    }
+
+    #[test]
+    fn read_timeout_rejects_invalid_seconds() {
+        assert_eq!(read_timeout("1.5").unwrap(), Duration::from_millis(1500));
+        assert_eq!(read_timeout("0").unwrap(), Duration::ZERO);
+        for s in ["-1", "NaN", "inf", "1e300"] {
+            assert!(
+                matches!(read_timeout(s), Err(ViewerError::Other(_))),
+                "{}",
+                s
+            );
+        }
+        assert!(matches!(
+            read_timeout("soon"),
+            Err(ViewerError::ParseFloat(_))
+        ));
+    }
+
+    #[test]
+    fn bounds_columns_needs_a_known_dimension() {
+        let directory = Path::new("/nonexistent");
+        let bounds = (vec![0.0, 0.0], vec![1.0, 1.0]);
+        assert_eq!(
+            bounds_columns(directory, Some(&[2, 4]), Some(3), None, None).unwrap(),
+            vec![2, 4]
+        );
+        assert_eq!(
+            bounds_columns(directory, None, Some(3), Some(&bounds), None).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            bounds_columns(directory, None, None, Some(&bounds), None).unwrap(),
+            vec![1, 2]
+        );
+        assert!(bounds_columns(directory, None, None, None, None).is_err());
+    }
+
+    #[test]
+    fn select_dimension_checks_the_bounds() {
+        let bounds = |n: usize| vec![0.0; n];
+        assert_eq!(
+            select_dimension("b", bounds(3), bounds(3), Some(2)).unwrap(),
+            (bounds(2), bounds(2))
+        );
+        assert_eq!(
+            select_dimension("b", bounds(1), bounds(1), None).unwrap(),
+            (bounds(1), bounds(1))
+        );
+        assert!(select_dimension("b", bounds(3), bounds(2), None).is_err());
+        assert!(select_dimension("b", bounds(2), bounds(2), Some(3)).is_err());
+        assert!(select_dimension("b", bounds(4), bounds(4), None).is_err());
+        assert!(select_dimension("b", bounds(0), bounds(0), None).is_err());
+    }
+
+    #[test]
+    fn gnuplot_source_quotes_gzip_pipes() {
+        assert_eq!(gnuplot_source(Path::new("/runs/0.dat")), "\"/runs/0.dat\"");
+        assert_eq!(
+            gnuplot_source(Path::new("/runs/it's here/0.dat.gz")),
+            "\"< gzip -dc '/runs/it'\\\\''s here/0.dat.gz'\""
+        );
+    }
+
+    #[test]
+    fn concat_quote_escapes_quotes() {
+        assert_eq!(concat_quote(Path::new("/out/1.png")), "'/out/1.png'");
+        assert_eq!(
+            concat_quote(Path::new("/runs/it's here/1.png")),
+            "'/runs/it'\\''s here/1.png'"
+        );
+    }
+
+    #[test]
+    fn parse_meta_reads_both_bounds_forms() {
+        let meta = parse_meta(
+            r#"{"sample_number": 100, "sample_time": 0.01, "dimension": 2,
+                "bounds": {"min": [-1, -2], "max": [1, 2]}}"#,
+        )
+        .unwrap();
+        assert_eq!(meta.sample_number, 100);
+        assert_eq!(meta.sample_time, 0.01);
+        assert_eq!(meta.bounds, Some((vec![-1.0, -2.0], vec![1.0, 2.0])));
+        let meta = parse_meta(
+            r#"{"sample_number": 3, "sample_time": 1, "bounds": [[0, 0, 0], [1, 1, 1]]}"#,
+        )
+        .unwrap();
+        assert_eq!(meta.bounds, Some((vec![0.0; 3], vec![1.0; 3])));
+        let meta = parse_meta(r#"{"sample_number": 3, "sample_time": 1}"#).unwrap();
+        assert_eq!(meta.bounds, None);
+    }
+
+    #[test]
+    fn parse_meta_rejects_invalid_fields() {
+        let error = |s| parse_meta(s).unwrap_err();
+        assert_eq!(
+            error(r#"{"sample_number": -1, "sample_time": 1}"#),
+            "sample_number must be a non-negative integer"
+        );
+        assert_eq!(
+            error(r#"{"sample_number": 1}"#),
+            "sample_time must be a number"
+        );
+        assert_eq!(
+            error(r#"{"sample_number": 1, "sample_time": 1, "bounds": [[0], [1, 1]]}"#),
+            "min and max bounds differ in dimension"
+        );
+        assert_eq!(
+            error(r#"{"sample_number": 1, "sample_time": 1, "bounds": {"min": [0]}}"#),
+            "max bounds must be an array of numbers"
+        );
+        assert_eq!(
+            error(
+                r#"{"sample_number": 1, "sample_time": 1, "dimension": 3,
+                    "bounds": [[0, 0], [1, 1]]}"#
+            ),
+            "dimension 3 does not match 2 bounds"
+        );
+        assert!(parse_meta("{").is_err());
+    }
+
+    #[test]
+    fn loop_sequence_plays_pingpong_without_repeating_the_turns() {
+        assert_eq!(
+            LoopMode::PingPong.sequence(&[1, 2, 3, 4]),
+            vec![1, 2, 3, 4, 3, 2]
+        );
+        assert_eq!(LoopMode::PingPong.sequence(&[1, 2]), vec![1, 2]);
+        assert_eq!(LoopMode::PingPong.sequence(&[]), Vec::<usize>::new());
+        assert_eq!(LoopMode::Repeat.sequence(&[1, 2, 3]), vec![1, 2, 3]);
+        assert_eq!(LoopMode::None.sequence(&[5]), vec![5]);
+    }
+
+    #[test]
+    fn fit_aspect_widens_the_short_side() {
+        // too narrow for 2:1, x grows by 2 around its center
+        assert_eq!(
+            fit_aspect((vec![0.0, 0.0], vec![2.0, 2.0]), 2.0),
+            (vec![-1.0, 0.0], vec![3.0, 2.0])
+        );
+        // too wide for 1:1, y grows instead
+        assert_eq!(
+            fit_aspect((vec![0.0, 0.0], vec![4.0, 2.0]), 1.0),
+            (vec![0.0, -1.0], vec![4.0, 3.0])
+        );
+        assert_eq!(
+            fit_aspect((vec![0.0, 0.0], vec![2.0, 1.0]), 2.0),
+            (vec![0.0, 0.0], vec![2.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn subsample_keeps_lines_in_order() {
+        let lines: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let kept = subsample(lines.clone(), 10);
+        assert_eq!(kept.len(), 10);
+        let indices: Vec<usize> = kept.iter().map(|l| l.parse().unwrap()).collect();
+        assert!(indices.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(subsample(lines.clone(), 10), kept);
+        assert_eq!(subsample(lines.clone(), 100), lines);
+        assert_eq!(subsample(lines[..3].to_vec(), 10), lines[..3].to_vec());
+        assert!(subsample(lines, 0).is_empty());
+    }
+
+    #[test]
+    fn read_sizes_accepts_every_form() {
+        assert_eq!(read_sizes("1920,1080").unwrap(), vec![(1920.0, 1080.0)]);
+        assert_eq!(read_sizes("512").unwrap(), vec![(512.0, 512.0)]);
+        assert_eq!(read_sizes(" 640 x 480 ").unwrap(), vec![(640.0, 480.0)]);
+        assert_eq!(
+            read_sizes("1920x1080,720").unwrap(),
+            vec![(1920.0, 1080.0), (720.0, 720.0)]
+        );
+        assert_eq!(
+            read_sizes("1920,1080;1280x720").unwrap(),
+            vec![(1920.0, 1080.0), (1280.0, 720.0)]
+        );
+    }
+
+    #[test]
+    fn read_sizes_rejects_malformed_sizes() {
+        for s in [
+            "", "0,10", "-5,5", "1.5,2", "1,2,3", "1920;", "big", "1920,",
+        ] {
+            assert!(
+                matches!(read_sizes(s), Err(ViewerError::Other(_))),
+                "{:?}",
+                s
+            );
+        }
+    }
+}
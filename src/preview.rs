@@ -0,0 +1,71 @@
+//! A text rendering of one frame for `--ascii-preview`, drawn without gnuplot
+
+/// Characters of grid cells holding more and more points
+const SHADES: &[char] = &['.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Draws the points within the bounds into a `width` by `height` character
+/// grid framed by a border, viewed along the z axis
+///
+/// 1D points are drawn as value against their index. Returns the lines and the
+/// number of points outside the bounds.
+pub fn render(
+    points: &[Vec<f64>],
+    min_bounds: &[f64],
+    max_bounds: &[f64],
+    width: usize,
+    height: usize,
+) -> (Vec<String>, usize) {
+    let mut counts = vec![vec![0usize; width]; height];
+    let mut outside = 0;
+    let last = points.len().saturating_sub(1).max(1) as f64;
+    for (n, point) in points.iter().enumerate() {
+        let (x, y, x_range, y_range) = if point.len() == 1 {
+            (
+                n as f64,
+                point[0],
+                (0.0, last),
+                (min_bounds[0], max_bounds[0]),
+            )
+        } else {
+            (
+                point[0],
+                point[1],
+                (min_bounds[0], max_bounds[0]),
+                (min_bounds[1], max_bounds[1]),
+            )
+        };
+        match (cell(x, x_range, width), cell(y, y_range, height)) {
+            // rows are printed from the top, where y is largest
+            (Some(column), Some(row)) => counts[height - 1 - row][column] += 1,
+            _ => outside += 1,
+        }
+    }
+    let max_count = counts.iter().flatten().cloned().max().unwrap_or(0);
+    let border = format!("+{}+", "-".repeat(width));
+    let mut lines = vec![border.clone()];
+    for row in counts {
+        let cells: String = row
+            .into_iter()
+            .map(|count| match count {
+                0 => ' ',
+                // scale the densest cell to the last shade
+                count => SHADES[(count - 1) * SHADES.len() / max_count],
+            })
+            .collect();
+        lines.push(format!("|{}|", cells));
+    }
+    lines.push(border);
+    (lines, outside)
+}
+
+/// Index of the cell of `value` among `cells` equal parts of `range`
+fn cell(value: f64, (min, max): (f64, f64), cells: usize) -> Option<usize> {
+    if !(min..=max).contains(&value) || cells == 0 {
+        return None;
+    }
+    if max == min {
+        return Some(cells / 2);
+    }
+    let index = ((value - min) / (max - min) * cells as f64) as usize;
+    Some(index.min(cells - 1))
+}
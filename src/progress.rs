@@ -0,0 +1,80 @@
+//! A minimal progress bar drawn on stderr
+
+use crate::color::{self, Color};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const BAR_WIDTH: usize = 40;
+
+pub struct ProgressBar {
+    total: usize,
+    done: usize,
+    start: Instant,
+}
+
+impl ProgressBar {
+    pub fn new(total: usize) -> ProgressBar {
+        let bar = ProgressBar {
+            total,
+            done: 0,
+            start: Instant::now(),
+        };
+        bar.draw();
+        bar
+    }
+
+    /// Marks one more frame as completed and redraws the bar
+    pub fn inc(&mut self) {
+        self.done += 1;
+        self.draw();
+    }
+
+    /// Ends the line of the bar so following output starts on a new line
+    pub fn finish(&self) {
+        eprintln!();
+    }
+
+    /// Estimates the remaining time from the average duration per frame
+    fn eta(&self) -> Option<Duration> {
+        if self.done == 0 {
+            None
+        } else {
+            let per_frame = self.start.elapsed() / self.done as u32;
+            Some(per_frame * (self.total - self.done) as u32)
+        }
+    }
+
+    fn draw(&self) {
+        let filled = (BAR_WIDTH * self.done)
+            .checked_div(self.total)
+            .unwrap_or(BAR_WIDTH);
+        let eta = match self.eta() {
+            Some(eta) => format_duration(eta),
+            None => "--:--:--".to_owned(),
+        };
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        // the bar is cosmetic, so failing to draw it is not an error
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {}/{} elapsed {} ETA {}",
+            color::paint_stderr(&"#".repeat(filled), Color::Green),
+            "-".repeat(BAR_WIDTH - filled),
+            self.done,
+            self.total,
+            format_duration(self.start.elapsed()),
+            eta
+        );
+        let _ = stderr.flush();
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
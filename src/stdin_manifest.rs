@@ -0,0 +1,132 @@
+//! Frames of `--stdin-manifest` mode, read from stdin as newline-delimited JSON
+//! records like `{"index": 3, "path": "out/3.dat", "time": 1.5}`
+//!
+//! A frame is submitted as soon as its record is read. `path` is relative to
+//! the data directory and defaults to `{index}.dat`, `time` defaults to the
+//! index times the sample time. Indices before `--sample-start` are rejected.
+//! Reading stops at the end of stdin.
+
+use crate::{json, sample_path, ViewerError};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, RwLock};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub path: PathBuf,
+    /// Simulation time of the frame in seconds
+    pub time: Option<f64>,
+}
+
+/// Records by frame index, shared with the workers rendering them
+pub type Records = Arc<RwLock<HashMap<usize, Record>>>;
+
+/// Parses one line of the manifest
+pub fn parse_record(line: &str, directory: &Path) -> Result<(usize, Record), String> {
+    let value: json::Value = line.parse()?;
+    let index = value
+        .get("index")
+        .and_then(json::Value::as_usize)
+        .ok_or("index must be a non-negative integer")?;
+    let path = match value.get("path") {
+        None | Some(json::Value::Null) => sample_path(directory, index),
+        Some(path) => directory.join(path.as_str().ok_or("path must be a string")?),
+    };
+    let time = match value.get("time") {
+        None | Some(json::Value::Null) => None,
+        Some(time) => Some(time.as_f64().ok_or("time must be a number")?),
+    };
+    Ok((index, Record { path, time }))
+}
+
+pub struct ManifestReader {
+    lines: mpsc::Receiver<io::Result<String>>,
+    directory: PathBuf,
+    /// Index of the first sample, records of earlier frames are rejected
+    sample_start: usize,
+    records: Records,
+    /// Indices of the records read so far, in order of arrival
+    arrived: Vec<usize>,
+    line_number: usize,
+    stopped: bool,
+}
+
+impl ManifestReader {
+    /// Starts reading stdin in the background
+    pub fn new(directory: &Path, sample_start: usize, records: Records) -> ManifestReader {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                // the render is over once the receiver is dropped
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        ManifestReader {
+            lines: rx,
+            directory: directory.to_owned(),
+            sample_start,
+            records,
+            arrived: Vec::new(),
+            line_number: 0,
+            stopped: false,
+        }
+    }
+
+    /// Whether stdin ended or reading was stopped, no more frames are returned
+    /// by `poll` then
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Stops reading, e.g. after Ctrl-C
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    pub fn arrived(&self) -> &[usize] {
+        &self.arrived
+    }
+
+    /// Returns the frames whose records were read since the last poll
+    pub fn poll(&mut self) -> Result<Vec<usize>, ViewerError> {
+        let mut ready = Vec::new();
+        while !self.stopped {
+            let line = match self.lines.try_recv() {
+                Ok(line) => line?,
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.stopped = true;
+                    break;
+                },
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (index, record) = parse_record(&line, &self.directory).map_err(|e| {
+                ViewerError::Other(format!("stdin manifest line {}: {}", self.line_number, e))
+            })?;
+            if index < self.sample_start {
+                return Err(ViewerError::Other(format!(
+                    "stdin manifest line {}: frame {} is before the first sample {}",
+                    self.line_number, index, self.sample_start
+                )));
+            }
+            let mut records = self.records.write().expect("manifest lock poisoned");
+            if records.contains_key(&index) {
+                return Err(ViewerError::Other(format!(
+                    "stdin manifest line {}: frame {} is listed twice",
+                    self.line_number, index
+                )));
+            }
+            records.insert(index, record);
+            self.arrived.push(index);
+            ready.push(index);
+        }
+        Ok(ready)
+    }
+}
@@ -0,0 +1,61 @@
+//! Wall time statistics of rendered frames
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct Timings {
+    /// Frame index and render time of every successful frame
+    frames: Vec<(usize, Duration)>,
+    start: Instant,
+}
+
+impl Timings {
+    pub fn new() -> Timings {
+        Timings {
+            frames: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, i: usize, time: Duration) {
+        self.frames.push((i, time));
+    }
+
+    /// Prints min, max, mean and median frame time and the overall throughput
+    pub fn print_summary(&self) {
+        if self.frames.is_empty() {
+            return;
+        }
+        let mut times: Vec<f64> = self.frames.iter().map(|(_, t)| t.as_secs_f64()).collect();
+        times.sort_by(|a, b| a.partial_cmp(b).expect("frame time is NaN"));
+        let n = times.len();
+        // both indices are the middle one if n is odd
+        let median = (times[(n - 1) / 2] + times[n / 2]) / 2.0;
+        println!(
+            "frame time: min {:.3} s, max {:.3} s, mean {:.3} s, median {:.3} s",
+            times[0],
+            times[n - 1],
+            times.iter().sum::<f64>() / n as f64,
+            median
+        );
+        println!(
+            "rendered {} frames at {:.2} frames per second",
+            n,
+            n as f64 / self.start.elapsed().as_secs_f64()
+        );
+    }
+
+    /// Writes `frame,seconds` lines ordered by frame
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut frames = self.frames.clone();
+        frames.sort_unstable_by_key(|&(i, _)| i);
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "frame,seconds")?;
+        for (i, time) in frames {
+            writeln!(writer, "{},{}", i, time.as_secs_f64())?;
+        }
+        writer.flush()
+    }
+}
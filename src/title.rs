@@ -0,0 +1,167 @@
+//! Templates of the per frame plot title
+//!
+//! A template is literal text with tokens substituted per frame:
+//!
+//! - `{time}` or `{time:.N}`: time point of the frame, with `N` decimals
+//! - `{unit}`: label of the time unit
+//! - `{frame}`: index of the frame
+//! - `{total}`: index of the last sample
+//!
+//! `{{` and `}}` stand for literal braces.
+
+use crate::ViewerError;
+use std::str::FromStr;
+
+pub const DEFAULT_TITLE_FORMAT: &str = "time = {time:.19} {unit}";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Time(Option<usize>),
+    Unit,
+    Frame,
+    Total,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleFormat {
+    parts: Vec<Part>,
+}
+
+impl TitleFormat {
+    pub fn render(&self, time: f64, unit: &str, frame: usize, total: usize) -> String {
+        let mut title = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => title.push_str(s),
+                Part::Time(Some(precision)) => title.push_str(&format!("{:.*}", precision, time)),
+                Part::Time(None) => title.push_str(&time.to_string()),
+                Part::Unit => title.push_str(unit),
+                Part::Frame => title.push_str(&frame.to_string()),
+                Part::Total => title.push_str(&total.to_string()),
+            }
+        }
+        title
+    }
+}
+
+fn parse_token(token: &str) -> Result<Part, ViewerError> {
+    let mut parts = token.splitn(2, ':');
+    let name = parts.next().unwrap_or_default();
+    match (name, parts.next()) {
+        ("time", None) => Ok(Part::Time(None)),
+        ("time", Some(spec)) if spec.starts_with('.') => Ok(Part::Time(Some(spec[1..].parse()?))),
+        ("unit", None) => Ok(Part::Unit),
+        ("frame", None) => Ok(Part::Frame),
+        ("total", None) => Ok(Part::Total),
+        _ => Err(ViewerError::Other(format!(
+            "unknown title token {{{}}}, expected {{time}}, {{time:.N}}, {{unit}}, {{frame}} or \
+             {{total}}",
+            token
+        ))),
+    }
+}
+
+impl FromStr for TitleFormat {
+    type Err = ViewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut token = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => token.push(c),
+                            None => {
+                                return Err(ViewerError::Other(format!(
+                                    "unmatched '{{' in title format {:?}",
+                                    s
+                                )))
+                            },
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(literal.split_off(0)));
+                    }
+                    parts.push(parse_token(&token)?);
+                },
+                '}' => {
+                    return Err(ViewerError::Other(format!(
+                        "unmatched '}}' in title format {:?}",
+                        s
+                    )))
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(TitleFormat { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: &str) -> String {
+        format
+            .parse::<TitleFormat>()
+            .unwrap()
+            .render(1.23456, "s", 7, 100)
+    }
+
+    #[test]
+    fn substitutes_tokens() {
+        assert_eq!(render("t = {time:.2} {unit}"), "t = 1.23 s");
+        assert_eq!(render("{time}"), "1.23456");
+        assert_eq!(render("frame {frame}/{total}"), "frame 7/100");
+        assert_eq!(render("no tokens"), "no tokens");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn keeps_escaped_braces() {
+        assert_eq!(render("{{frame}} = {frame}"), "{frame} = 7");
+        assert_eq!(render("}}{{"), "}{");
+    }
+
+    #[test]
+    fn rejects_invalid_formats() {
+        for s in [
+            "{speed}",
+            "{time:2}",
+            "{time:.x}",
+            "{frame:.2}",
+            "{time",
+            "time}",
+        ] {
+            assert!(s.parse::<TitleFormat>().is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn parses_the_default() {
+        assert_eq!(
+            DEFAULT_TITLE_FORMAT
+                .parse::<TitleFormat>()
+                .unwrap()
+                .render(0.5, "s", 0, 0),
+            "time = 0.5000000000000000000 s"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Incremental rendering of `--watch` mode
+//!
+//! A frame is submitted once the sample files after it appeared, since a
+//! simulator may still be writing the newest ones. The sentinel file `_done.txt`
+//! marks the simulation as finished, which releases the last frame. Watching
+//! stops when all frames are submitted, the sentinel appeared or no new frame
+//! became ready within the timeout, the latter also releasing the last frame.
+
+use crate::{sample_paths, Species};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Name of the file written to the data directory when the simulation is done
+pub const SENTINEL: &str = "_done.txt";
+
+pub struct Watcher {
+    directory: PathBuf,
+    species: Vec<Species>,
+    /// Frames not submitted yet, in order
+    pending: VecDeque<usize>,
+    timeout: Duration,
+    last_ready: Instant,
+    stopped: bool,
+}
+
+impl Watcher {
+    pub fn new(
+        directory: &Path,
+        species: &[Species],
+        frames: &[usize],
+        timeout: Duration,
+    ) -> Watcher {
+        Watcher {
+            directory: directory.to_owned(),
+            species: species.to_vec(),
+            pending: frames.iter().cloned().collect(),
+            timeout,
+            last_ready: Instant::now(),
+            stopped: frames.is_empty(),
+        }
+    }
+
+    /// Whether watching stopped, no more frames are returned by `poll` then
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Stops watching, e.g. after Ctrl-C
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Frames that were never submitted because watching stopped before their
+    /// sample files appeared
+    pub fn remaining(&self) -> &VecDeque<usize> {
+        &self.pending
+    }
+
+    /// Returns the frames whose sample files are complete since the last poll
+    pub fn poll(&mut self) -> Vec<usize> {
+        let mut ready = Vec::new();
+        if self.stopped {
+            return ready;
+        }
+        // after the timeout the simulation is assumed to be gone, so the files
+        // that exist are complete
+        let done =
+            self.directory.join(SENTINEL).exists() || self.last_ready.elapsed() > self.timeout;
+        let (directory, species) = (&self.directory, &self.species);
+        let exists = |i| {
+            sample_paths(directory, i, species)
+                .iter()
+                .all(|path| path.exists())
+        };
+        while let Some(&i) = self.pending.front() {
+            if exists(i) && (done || exists(i + 1)) {
+                ready.push(i);
+                self.pending.pop_front();
+            } else {
+                break;
+            }
+        }
+        if !ready.is_empty() {
+            self.last_ready = Instant::now();
+        }
+        if self.pending.is_empty() || done {
+            self.stopped = true;
+        }
+        ready
+    }
+}